@@ -0,0 +1,221 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+interface IERC20 {
+    function balanceOf(address owner) external view returns (uint256);
+}
+
+interface IERC20Permit {
+    function permit(
+        address owner,
+        address spender,
+        uint256 value,
+        uint256 deadline,
+        uint8 v,
+        bytes32 r,
+        bytes32 s
+    ) external;
+}
+
+interface IPermit2 {
+    struct TokenPermissions {
+        address token;
+        uint256 amount;
+    }
+
+    struct PermitTransferFrom {
+        TokenPermissions permitted;
+        uint256 nonce;
+        uint256 deadline;
+    }
+
+    struct SignatureTransferDetails {
+        address to;
+        uint256 requestedAmount;
+    }
+
+    function permitTransferFrom(
+        PermitTransferFrom calldata permit,
+        SignatureTransferDetails calldata transferDetails,
+        address owner,
+        bytes calldata signature
+    ) external;
+}
+
+interface IEscrow {
+    struct SolverTransferData {
+        string intentId;
+        string solverOut;
+    }
+
+    function sendFundsToUser(SolverTransferData calldata solverTransferData) external payable;
+}
+
+/// @notice Fills an intent in one transaction: bridge token -> token_out swap,
+/// token_out approval to the escrow, sendFundsToUser and, on single domain
+/// intents, the token_in -> bridge token swap back. Any failing step reverts
+/// the whole fill. The escrow sees this contract as the solver.
+contract SolverExecutor {
+    struct Swap {
+        address target; // router, skipped when zero
+        address spender; // router allowance target
+        address tokenIn;
+        uint256 amountIn; // max token_in the router may pull
+        uint256 value;
+        bytes data;
+    }
+
+    struct Fill {
+        address escrow;
+        string intentId;
+        string solverOut;
+        address bridgeToken; // USDT
+        uint256 bridgeAmount; // bridge token pulled from the owner
+        address tokenOut;
+        uint256 amountOut; // token_out approved to the escrow
+        Swap swapOut; // bridge token -> token_out
+        Swap swapBack; // token_in -> bridge token
+        address tokenIn;
+    }
+
+    // signature the owner gives instead of approving the bridge token to this contract
+    struct PullPermit {
+        uint8 kind; // PERMIT_ERC2612 or PERMIT_PERMIT2
+        uint256 nonce; // Permit2 only, unordered
+        uint256 deadline;
+        bytes signature; // r, s, v
+    }
+
+    uint8 public constant PERMIT_ERC2612 = 1;
+    uint8 public constant PERMIT_PERMIT2 = 2;
+    address public constant PERMIT2 = 0x000000000022D473030F116dDEE9F6B43aC78BA3;
+
+    address public immutable owner;
+
+    error NotOwner();
+    error InvalidPermit();
+    error TokenCallFailed(address token, bytes4 selector);
+    error SwapFailed(address target, bytes reason);
+    error EscrowFailed(bytes reason);
+
+    modifier onlyOwner() {
+        if (msg.sender != owner) revert NotOwner();
+        _;
+    }
+
+    constructor(address owner_) {
+        owner = owner_;
+    }
+
+    receive() external payable {}
+
+    /// @notice Pulls the bridge token through the owner's allowance to this contract.
+    function fill(Fill calldata f) external payable onlyOwner {
+        if (f.bridgeAmount > 0) {
+            _call(
+                f.bridgeToken,
+                abi.encodeWithSelector(0x23b872dd, owner, address(this), f.bridgeAmount) // transferFrom
+            );
+        }
+        _fill(f);
+    }
+
+    /// @notice Pulls the bridge token with an EIP-2612 permit or a Permit2
+    /// signature transfer signed by the owner, no approve transaction needed.
+    function fillWithPermit(Fill calldata f, PullPermit calldata p) external payable onlyOwner {
+        if (p.kind == PERMIT_ERC2612) {
+            if (p.signature.length != 65) revert InvalidPermit();
+            IERC20Permit(f.bridgeToken).permit(
+                owner,
+                address(this),
+                f.bridgeAmount,
+                p.deadline,
+                uint8(p.signature[64]),
+                bytes32(p.signature[0:32]),
+                bytes32(p.signature[32:64])
+            );
+            _call(
+                f.bridgeToken,
+                abi.encodeWithSelector(0x23b872dd, owner, address(this), f.bridgeAmount) // transferFrom
+            );
+        } else if (p.kind == PERMIT_PERMIT2) {
+            IPermit2(PERMIT2).permitTransferFrom(
+                IPermit2.PermitTransferFrom(
+                    IPermit2.TokenPermissions(f.bridgeToken, f.bridgeAmount), p.nonce, p.deadline
+                ),
+                IPermit2.SignatureTransferDetails(address(this), f.bridgeAmount),
+                owner,
+                p.signature
+            );
+        } else {
+            revert InvalidPermit();
+        }
+        _fill(f);
+    }
+
+    function _fill(Fill calldata f) internal {
+        _swap(f.swapOut);
+
+        _approve(f.tokenOut, f.escrow, f.amountOut);
+        (bool ok, bytes memory reason) = f.escrow.call(
+            abi.encodeCall(IEscrow.sendFundsToUser, (IEscrow.SolverTransferData(f.intentId, f.solverOut)))
+        );
+        if (!ok) revert EscrowFailed(reason);
+        _approve(f.tokenOut, f.escrow, 0);
+
+        _swap(f.swapBack);
+
+        // everything left (swap dust, token_in, bridge token) goes back to the owner
+        _sweep(f.bridgeToken);
+        _sweep(f.tokenOut);
+        _sweep(f.tokenIn);
+        if (address(this).balance > 0) {
+            payable(owner).transfer(address(this).balance);
+        }
+    }
+
+    /// @notice Recovers tokens received as solver on cross-chain intents, or stuck here.
+    function sweep(address token) external onlyOwner {
+        if (token == address(0)) {
+            payable(owner).transfer(address(this).balance);
+        } else {
+            _sweep(token);
+        }
+    }
+
+    function _swap(Swap calldata s) internal {
+        if (s.target == address(0)) return;
+
+        _approve(s.tokenIn, s.spender, s.amountIn);
+        (bool ok, bytes memory reason) = s.target.call{value: s.value}(s.data);
+        if (!ok) revert SwapFailed(s.target, reason);
+        _approve(s.tokenIn, s.spender, 0);
+    }
+
+    // resets to zero first, USDT rejects changing a non-zero allowance
+    function _approve(address token, address spender, uint256 amount) internal {
+        if (token == address(0)) return;
+
+        _call(token, abi.encodeWithSelector(0x095ea7b3, spender, 0)); // approve
+        if (amount > 0) {
+            _call(token, abi.encodeWithSelector(0x095ea7b3, spender, amount));
+        }
+    }
+
+    function _sweep(address token) internal {
+        if (token == address(0)) return;
+
+        uint256 balance = IERC20(token).balanceOf(address(this));
+        if (balance > 0) {
+            _call(token, abi.encodeWithSelector(0xa9059cbb, owner, balance)); // transfer
+        }
+    }
+
+    // tolerates tokens like USDT that return nothing instead of a bool
+    function _call(address token, bytes memory data) internal {
+        (bool ok, bytes memory ret) = token.call(data);
+        if (!ok || (ret.length > 0 && !abi.decode(ret, (bool)))) {
+            revert TokenCallFailed(token, bytes4(data));
+        }
+    }
+}
@@ -0,0 +1,249 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import {SolverExecutor} from "../SolverExecutor.sol";
+
+// cd contracts && forge test
+
+contract MockToken {
+    mapping(address => uint256) public balanceOf;
+    mapping(address => mapping(address => uint256)) public allowance;
+
+    function mint(address to, uint256 amount) external {
+        balanceOf[to] += amount;
+    }
+
+    function approve(address spender, uint256 amount) external returns (bool) {
+        allowance[msg.sender][spender] = amount;
+        return true;
+    }
+
+    function transfer(address to, uint256 amount) external returns (bool) {
+        balanceOf[msg.sender] -= amount;
+        balanceOf[to] += amount;
+        return true;
+    }
+
+    function transferFrom(address from, address to, uint256 amount) external returns (bool) {
+        allowance[from][msg.sender] -= amount;
+        balanceOf[from] -= amount;
+        balanceOf[to] += amount;
+        return true;
+    }
+}
+
+/// @notice Swaps at a fixed price: pulls amountIn of tokenIn, mints amountOut of tokenOut.
+contract MockRouter {
+    function swap(MockToken tokenIn, uint256 amountIn, MockToken tokenOut, uint256 amountOut) external {
+        tokenIn.transferFrom(msg.sender, address(this), amountIn);
+        tokenOut.mint(msg.sender, amountOut);
+    }
+}
+
+/// @notice The escrow of abi/Escrow.json: only the winner set by the auctioneer
+/// may pay the user, token_in goes to solverOut on single domain intents.
+contract MockEscrow {
+    struct SolverTransferData {
+        string intentId;
+        string solverOut;
+    }
+
+    struct IntentInfo {
+        address tokenIn;
+        uint256 amountIn;
+        address srcUser;
+        string tokenOut;
+        string amountOut;
+        string dstUser;
+        string winnerSolver;
+        uint256 timeout;
+        bool singleDomain;
+    }
+
+    address public immutable auctioneer;
+    mapping(string => IntentInfo) internal intents;
+
+    event FundsSentToUser(
+        string intentId, address indexed solver, address indexed user, address tokenOut, uint256 amountOut
+    );
+
+    error NotAuctioneer();
+    error NotWinnerSolver();
+    error IntentNotFound(string intentId);
+
+    constructor() {
+        auctioneer = msg.sender;
+    }
+
+    function getIntentInfo(string memory intentId) external view returns (IntentInfo memory) {
+        return intents[intentId];
+    }
+
+    function escrowFunds(string memory intentId, IntentInfo memory intentInfo) external payable {
+        MockToken(intentInfo.tokenIn).transferFrom(msg.sender, address(this), intentInfo.amountIn);
+        intents[intentId] = intentInfo;
+    }
+
+    function updateAuctionData(string memory intentId, string memory winnerSolver, string memory amountOut)
+        external
+    {
+        if (msg.sender != auctioneer) revert NotAuctioneer();
+        intents[intentId].winnerSolver = winnerSolver;
+        intents[intentId].amountOut = amountOut;
+    }
+
+    function sendFundsToUser(SolverTransferData memory solverTransferData) external payable {
+        IntentInfo storage info = intents[solverTransferData.intentId];
+        if (info.srcUser == address(0)) revert IntentNotFound(solverTransferData.intentId);
+        if (keccak256(bytes(toHex(msg.sender))) != keccak256(bytes(info.winnerSolver))) {
+            revert NotWinnerSolver();
+        }
+
+        address tokenOut = parseAddress(info.tokenOut);
+        address user = parseAddress(info.dstUser);
+        uint256 amountOut = parseUint(info.amountOut);
+        MockToken(tokenOut).transferFrom(msg.sender, user, amountOut);
+        if (info.singleDomain) {
+            MockToken(info.tokenIn).transfer(parseAddress(solverTransferData.solverOut), info.amountIn);
+        }
+        emit FundsSentToUser(solverTransferData.intentId, msg.sender, user, tokenOut, amountOut);
+    }
+}
+
+// lowercase 0x-prefixed hex, the way the solver formats addresses
+function toHex(address account) pure returns (string memory) {
+    bytes16 digits = "0123456789abcdef";
+    bytes memory s = new bytes(42);
+    s[0] = "0";
+    s[1] = "x";
+    for (uint256 i = 0; i < 20; i++) {
+        uint8 b = uint8(uint160(account) >> (8 * (19 - i)));
+        s[2 + 2 * i] = digits[b >> 4];
+        s[3 + 2 * i] = digits[b & 0x0f];
+    }
+    return string(s);
+}
+
+function parseAddress(string memory account) pure returns (address) {
+    bytes memory s = bytes(account);
+    uint160 value;
+    for (uint256 i = 2; i < 42; i++) {
+        uint8 c = uint8(s[i]);
+        uint8 digit = c >= 97 ? c - 87 : c >= 65 ? c - 55 : c - 48;
+        value = value * 16 + digit;
+    }
+    return address(value);
+}
+
+function parseUint(string memory amount) pure returns (uint256 value) {
+    bytes memory s = bytes(amount);
+    for (uint256 i = 0; i < s.length; i++) {
+        value = value * 10 + (uint8(s[i]) - 48);
+    }
+}
+
+contract Stranger {
+    function fill(SolverExecutor executor, SolverExecutor.Fill memory f) external {
+        executor.fill(f);
+    }
+}
+
+contract SolverExecutorTest {
+    MockToken usdt;
+    MockToken weth;
+    MockToken tokenOut;
+    MockRouter router;
+    MockEscrow escrow;
+    SolverExecutor executor;
+
+    address constant USER = address(0xBEEF);
+
+    function setUp() public {
+        usdt = new MockToken();
+        weth = new MockToken();
+        tokenOut = new MockToken();
+        router = new MockRouter();
+        escrow = new MockEscrow(); // this test is the auctioneer
+        executor = new SolverExecutor(address(this)); // and the solver wallet
+
+        usdt.mint(address(this), 10_000);
+        usdt.approve(address(executor), type(uint256).max);
+    }
+
+    /// @dev Escrows 5 WETH for 1000 token_out, won by `winner` on the same chain.
+    function escrowIntent(string memory intentId, address winner) internal {
+        weth.mint(address(this), 5);
+        weth.approve(address(escrow), 5);
+        MockEscrow.IntentInfo memory info;
+        info.tokenIn = address(weth);
+        info.amountIn = 5;
+        info.srcUser = address(this);
+        info.tokenOut = toHex(address(tokenOut));
+        info.dstUser = toHex(USER);
+        info.timeout = block.timestamp + 1 hours;
+        info.singleDomain = true;
+        escrow.escrowFunds(intentId, info);
+        escrow.updateAuctionData(intentId, toHex(winner), "1000");
+    }
+
+    /// @dev 900 USDT -> 1000 token_out to the user, 5 WETH back -> 950 USDT.
+    function singleDomainFill(string memory intentId) internal view returns (SolverExecutor.Fill memory f) {
+        f.escrow = address(escrow);
+        f.intentId = intentId;
+        f.solverOut = toHex(address(executor));
+        f.bridgeToken = address(usdt);
+        f.bridgeAmount = 900;
+        f.tokenOut = address(tokenOut);
+        f.amountOut = 1000;
+        f.swapOut.target = address(router);
+        f.swapOut.spender = address(router);
+        f.swapOut.tokenIn = address(usdt);
+        f.swapOut.amountIn = 900;
+        f.swapOut.data = abi.encodeCall(MockRouter.swap, (usdt, 900, tokenOut, 1000));
+        f.swapBack.target = address(router);
+        f.swapBack.spender = address(router);
+        f.swapBack.tokenIn = address(weth);
+        f.swapBack.amountIn = 5;
+        f.swapBack.data = abi.encodeCall(MockRouter.swap, (weth, 5, usdt, 950));
+        f.tokenIn = address(weth);
+    }
+
+    function testFillPaysTheUserAndSwapsTokenInBack() public {
+        escrowIntent("1", address(executor));
+
+        executor.fill(singleDomainFill("1"));
+
+        require(tokenOut.balanceOf(USER) == 1000, "user not paid");
+        require(usdt.balanceOf(address(this)) == 10_000 - 900 + 950, "bridge token not swept back");
+        require(usdt.balanceOf(address(executor)) == 0, "bridge token left in the executor");
+        require(weth.balanceOf(address(executor)) == 0, "token_in left in the executor");
+        require(tokenOut.balanceOf(address(executor)) == 0, "token_out left in the executor");
+        require(tokenOut.allowance(address(executor), address(escrow)) == 0, "escrow allowance left");
+    }
+
+    function testFillRevertsWhenTheExecutorIsNotTheWinner() public {
+        // the wallet won, so the escrow rejects the executor paying
+        escrowIntent("2", address(this));
+
+        try executor.fill(singleDomainFill("2")) {
+            revert("fill by an executor that didn't win went through");
+        } catch (bytes memory reason) {
+            bytes memory expected = abi.encodeWithSelector(
+                SolverExecutor.EscrowFailed.selector, abi.encodeWithSelector(MockEscrow.NotWinnerSolver.selector)
+            );
+            require(keccak256(reason) == keccak256(expected), "expected EscrowFailed(NotWinnerSolver())");
+        }
+        require(usdt.balanceOf(address(this)) == 10_000, "bridge token pulled by a reverted fill");
+    }
+
+    function testOnlyTheOwnerFills() public {
+        escrowIntent("3", address(executor));
+        Stranger stranger = new Stranger();
+
+        try stranger.fill(executor, singleDomainFill("3")) {
+            revert("fill by a stranger went through");
+        } catch (bytes memory reason) {
+            require(bytes4(reason) == SolverExecutor.NotOwner.selector, "expected NotOwner()");
+        }
+    }
+}
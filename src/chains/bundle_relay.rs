@@ -0,0 +1,351 @@
+pub mod bundle_relay {
+    use crate::chains::ethereum::ethereum_chain::EvmClient;
+    use crate::chains::nonce_manager::nonce_manager::{AccountLock, NONCE_MANAGER};
+    use crate::chains::revert::revert::decode_revert;
+    use crate::chains::tx_manager::tx_manager::{find_receipt, to_eip1559, TxError};
+    use ethers::prelude::*;
+    use ethers::types::transaction::eip2718::TypedTransaction;
+    use ethers::utils::keccak256;
+    use lazy_static::lazy_static;
+    use reqwest::Client;
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+    use std::env;
+
+    lazy_static! {
+        static ref RELAY_HTTP: Client = Client::new();
+    }
+
+    #[derive(thiserror::Error, Debug)]
+    pub enum BundleError {
+        #[error("reqwest: {0}")]
+        Reqwest(#[from] reqwest::Error),
+
+        #[error("relay: {0}")]
+        Relay(String),
+
+        #[error("{0}")]
+        Tx(#[from] TxError),
+
+        #[error("bundle simulation failed at tx {index}: {reason}")]
+        Simulation { index: usize, reason: String },
+
+        #[error("bundle not included after {0} blocks")]
+        NotIncluded(u64),
+    }
+
+    /// Blocks a bundle is resubmitted for before giving up, `BUNDLE_MAX_BLOCKS`.
+    fn max_blocks() -> u64 {
+        env::var("BUNDLE_MAX_BLOCKS")
+            .ok()
+            .and_then(|blocks| blocks.parse::<u64>().ok())
+            .unwrap_or(10)
+    }
+
+    /// Key the relay authenticates (and rates) us by, `BUNDLE_SIGNER_PKEY`,
+    /// else the solver wallet.
+    fn relay_signer(client: &EvmClient) -> Result<LocalWallet, BundleError> {
+        match env::var("BUNDLE_SIGNER_PKEY") {
+            Ok(private_key) => private_key
+                .parse()
+                .map_err(|e| BundleError::Relay(format!("Invalid BUNDLE_SIGNER_PKEY: {}", e))),
+            Err(_) => Ok(client.signer().clone()),
+        }
+    }
+
+    /// JSON-RPC `method` on the relay, signed with the Flashbots
+    /// `X-Flashbots-Signature: address:signature(keccak256(body))` header.
+    async fn relay_request(
+        relay_url: &str,
+        signer: &LocalWallet,
+        method: &str,
+        params: Value,
+    ) -> Result<Value, BundleError> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        })
+        .to_string();
+
+        let body_hash = format!("{:?}", H256::from(keccak256(body.as_bytes())));
+        let signature = signer
+            .sign_message(body_hash)
+            .await
+            .map_err(|e| BundleError::Tx(TxError::Signer(e.to_string())))?;
+
+        #[derive(Deserialize)]
+        struct RelayError {
+            message: String,
+        }
+        #[derive(Deserialize)]
+        struct RelayResponse {
+            result: Option<Value>,
+            error: Option<RelayError>,
+        }
+
+        let response: RelayResponse = RELAY_HTTP
+            .post(relay_url)
+            .header("Content-Type", "application/json")
+            .header(
+                "X-Flashbots-Signature",
+                format!("{:?}:0x{}", signer.address(), signature),
+            )
+            .body(body)
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        match response {
+            RelayResponse {
+                error: Some(RelayError { message }),
+                ..
+            } => Err(BundleError::Relay(message)),
+            RelayResponse { result, .. } => Ok(result.unwrap_or(Value::Null)),
+        }
+    }
+
+    /// `eth_sendBundle` for `block`.
+    async fn relay_bundle(
+        relay_url: &str,
+        signer: &LocalWallet,
+        raw_txs: &[Bytes],
+        block: U64,
+    ) -> Result<Value, BundleError> {
+        let params = json!([{
+            "txs": raw_txs,
+            "blockNumber": format!("{:#x}", block),
+        }]);
+        relay_request(relay_url, signer, "eth_sendBundle", params).await
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct CallBundleResult {
+        error: Option<String>,
+        // revert reason, raw revert data on some relays
+        revert: Option<String>,
+    }
+
+    #[derive(Deserialize, Debug)]
+    struct CallBundleResponse {
+        results: Vec<CallBundleResult>,
+    }
+
+    /// First failing transaction of an `eth_callBundle` response.
+    fn bundle_failure(response: &CallBundleResponse) -> Option<BundleError> {
+        let (index, result) = response
+            .results
+            .iter()
+            .enumerate()
+            .find(|(_, result)| result.error.is_some() || result.revert.is_some())?;
+
+        let reason = match (&result.revert, &result.error) {
+            (Some(revert), _) if revert.starts_with("0x") => {
+                match hex::decode(revert.trim_start_matches("0x")) {
+                    Ok(data) => decode_revert(&data).to_string(),
+                    Err(_) => revert.clone(),
+                }
+            }
+            (Some(revert), _) => revert.clone(),
+            (None, Some(error)) => error.clone(),
+            (None, None) => return None,
+        };
+        Some(BundleError::Simulation { index, reason })
+    }
+
+    /// `eth_callBundle` of the signed bundle on top of the latest block, as
+    /// if mined in `block`, so a bundle with a reverting transaction is never
+    /// relayed.
+    async fn simulate_bundle(
+        relay_url: &str,
+        signer: &LocalWallet,
+        raw_txs: &[Bytes],
+        block: U64,
+    ) -> Result<(), BundleError> {
+        let params = json!([{
+            "txs": raw_txs,
+            "blockNumber": format!("{:#x}", block),
+            "stateBlockNumber": "latest",
+        }]);
+        let result = relay_request(relay_url, signer, "eth_callBundle", params).await?;
+        let response: CallBundleResponse = serde_json::from_value(result)
+            .map_err(|e| BundleError::Relay(format!("Invalid eth_callBundle response: {}", e)))?;
+
+        match bundle_failure(&response) {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Signs `txs` with consecutive nonces, simulates them with
+    /// `eth_callBundle` and submits them as one bundle to `relay_url`, block
+    /// after block, until it lands or `BUNDLE_MAX_BLOCKS` pass. Nothing goes
+    /// through the public mempool, so the transactions land together, in
+    /// order, or not at all. The account's nonces stay locked until then, so
+    /// no public transaction queues up behind the bundle. Every tx needs its
+    /// gas limit set, later ones can't be estimated before the earlier ones run.
+    pub async fn send_bundle(
+        client: &EvmClient,
+        relay_url: &str,
+        txs: Vec<TypedTransaction>,
+    ) -> Result<Vec<TransactionReceipt>, BundleError> {
+        let chain_id = client.signer().chain_id();
+        let account = client.address();
+
+        let mut account_nonces = NONCE_MANAGER
+            .lock(client, chain_id, account)
+            .await
+            .map_err(|e| BundleError::Tx(TxError::Provider(e)))?;
+        let mut nonces = Vec::with_capacity(txs.len());
+        let result =
+            relay_until_included(client, relay_url, txs, &mut account_nonces, &mut nonces).await;
+
+        for nonce in &nonces {
+            account_nonces.sent(*nonce);
+        }
+        if result.is_err() && !nonces.is_empty() {
+            // whether any of the nonces got used is up to the node now
+            account_nonces.resync();
+        }
+        result
+    }
+
+    async fn relay_until_included(
+        client: &EvmClient,
+        relay_url: &str,
+        txs: Vec<TypedTransaction>,
+        account_nonces: &mut AccountLock,
+        nonces: &mut Vec<U256>,
+    ) -> Result<Vec<TransactionReceipt>, BundleError> {
+        let chain_id = client.signer().chain_id();
+        let account = client.address();
+        let signer = relay_signer(client)?;
+        let provider_error =
+            |e: ProviderError| BundleError::Tx(TxError::Provider(e.to_string()));
+
+        let (max_fee_per_gas, max_priority_fee_per_gas) = client
+            .provider()
+            .estimate_eip1559_fees(None)
+            .await
+            .map_err(provider_error)?;
+
+        let mut raw_txs = Vec::with_capacity(txs.len());
+        let mut hashes = Vec::with_capacity(txs.len());
+        for tx in txs {
+            let mut tx = to_eip1559(tx);
+            tx.from = Some(account);
+            tx.chain_id = Some(chain_id.into());
+            tx.max_fee_per_gas = Some(max_fee_per_gas);
+            tx.max_priority_fee_per_gas = Some(max_priority_fee_per_gas);
+
+            let nonce = account_nonces.reserve();
+            nonces.push(nonce);
+            tx.nonce = Some(nonce);
+
+            let typed = TypedTransaction::Eip1559(tx);
+            let signature = client
+                .signer()
+                .sign_transaction(&typed)
+                .await
+                .map_err(|e| BundleError::Tx(TxError::Signer(e.to_string())))?;
+            let raw_tx = typed.rlp_signed(&signature);
+            hashes.push(H256::from(keccak256(&raw_tx)));
+            raw_txs.push(raw_tx);
+        }
+
+        let first_block = client
+            .provider()
+            .get_block_number()
+            .await
+            .map_err(provider_error)?;
+        let max_blocks = max_blocks();
+
+        simulate_bundle(relay_url, &signer, &raw_txs, first_block + 1).await?;
+
+        for target in 1..=max_blocks {
+            let block = first_block + target;
+            if let Err(e) = relay_bundle(relay_url, &signer, &raw_txs, block).await {
+                println!("Failed to relay bundle for block {block}: {e}");
+            }
+
+            // wait for the target block to be mined
+            while client
+                .provider()
+                .get_block_number()
+                .await
+                .map_err(provider_error)?
+                < block
+            {
+                tokio::time::sleep(client.provider().get_interval()).await;
+            }
+
+            if find_receipt(client, &hashes[..1]).await?.is_some() {
+                let mut receipts = Vec::with_capacity(hashes.len());
+                for hash in &hashes {
+                    let receipt = find_receipt(client, &[*hash]).await?.ok_or_else(|| {
+                        BundleError::Relay(format!("{:?} missing from the bundle block", hash))
+                    })?;
+                    if receipt.status != Some(U64::from(1)) {
+                        return Err(TxError::Reverted(*hash).into());
+                    }
+                    receipts.push(receipt);
+                }
+                return Ok(receipts);
+            }
+        }
+
+        Err(BundleError::NotIncluded(max_blocks))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn failure(response: Value) -> Option<String> {
+            let response: CallBundleResponse = serde_json::from_value(response).unwrap();
+            bundle_failure(&response).map(|e| e.to_string())
+        }
+
+        #[test]
+        fn passing_bundle_has_no_failure() {
+            let response = json!({
+                "results": [{ "txHash": "0x01", "gasUsed": 21000 }, { "txHash": "0x02" }],
+                "bundleHash": "0x03",
+            });
+            assert_eq!(failure(response), None);
+        }
+
+        #[test]
+        fn reports_the_first_reverting_transaction() {
+            let response = json!({
+                "results": [
+                    { "txHash": "0x01" },
+                    { "txHash": "0x02", "error": "execution reverted", "revert": "STF" },
+                    { "txHash": "0x03", "error": "execution reverted" },
+                ],
+            });
+            assert_eq!(
+                failure(response).as_deref(),
+                Some("bundle simulation failed at tx 1: STF")
+            );
+        }
+
+        #[test]
+        fn decodes_raw_revert_data() {
+            // Error("STF")
+            let revert = "0x08c379a0\
+                0000000000000000000000000000000000000000000000000000000000000020\
+                0000000000000000000000000000000000000000000000000000000000000003\
+                5354460000000000000000000000000000000000000000000000000000000000";
+            let response = json!({
+                "results": [{ "txHash": "0x01", "error": "execution reverted", "revert": revert }],
+            });
+            assert_eq!(
+                failure(response).as_deref(),
+                Some("bundle simulation failed at tx 0: STF")
+            );
+        }
+    }
+}
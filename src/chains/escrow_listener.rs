@@ -0,0 +1,478 @@
+pub mod escrow_listener {
+    use crate::chains::ethereum::ethereum_chain::{
+        evm_context, evm_network, AuctionDataUpdatedFilter, CrossChainReleaseFilter, EscrowEvents,
+        EvmNetwork, FundsSentToUserFilter, IntentRefundedFilter, IntentStoredFilter, EVM_NETWORKS,
+    };
+    use crate::chains::{SOLVER_ADDRESSES, SOLVER_EXECUTOR};
+    use crate::PostIntentInfo;
+    use ethers::contract::EthLogDecode;
+    use ethers::prelude::*;
+    use futures::StreamExt;
+    use lazy_static::lazy_static;
+    use std::collections::HashMap;
+    use std::env;
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+    use tokio::sync::RwLock;
+
+    lazy_static! {
+        // <(network, intent_id), escrow events seen for it on that network>
+        pub static ref ESCROW_INTENTS: Arc<RwLock<HashMap<(&'static str, String), EscrowIntent>>> =
+            Arc::new(RwLock::new(HashMap::new()));
+    }
+
+    /// Blocks before the listener start that are scanned, so an intent stored
+    /// right before a (re)start is still seen.
+    const LOOKBACK_BLOCKS: u64 = 100;
+    /// Most blocks fetched per `eth_getLogs`, RPCs reject wider ranges.
+    const LOG_RANGE_BLOCKS: u64 = 1_000;
+    /// Events of intents older than this are dropped.
+    const RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+
+    /// What the escrow emitted for one intent, with the emitting transaction.
+    #[derive(Debug, Clone)]
+    pub struct EscrowIntent {
+        pub stored: Option<(IntentStoredFilter, TxHash)>,
+        pub auction: Option<(AuctionDataUpdatedFilter, TxHash)>,
+        pub funds_sent: Option<(FundsSentToUserFilter, TxHash)>,
+        pub released: Option<(CrossChainReleaseFilter, TxHash)>,
+        pub refunded: Option<(IntentRefundedFilter, TxHash)>,
+        last_seen: Instant,
+    }
+
+    /// Seconds to poll escrow logs over HTTP, `ESCROW_POLL_INTERVAL`.
+    fn poll_interval() -> Duration {
+        Duration::from_secs(
+            env::var("ESCROW_POLL_INTERVAL")
+                .ok()
+                .and_then(|secs| secs.parse::<u64>().ok())
+                .unwrap_or(12)
+                .max(1),
+        )
+    }
+
+    impl EscrowIntent {
+        /// Whether one of the solver's addresses paid the user.
+        pub fn sent_by_solver(&self) -> bool {
+            self.funds_sent
+                .as_ref()
+                .is_some_and(|(e, _)| solver_addresses().contains(&e.solver))
+        }
+    }
+
+    fn solver_addresses() -> Vec<Address> {
+        SOLVER_ADDRESSES
+            .first()
+            .and_then(|address| Address::from_str(address).ok())
+            .into_iter()
+            .chain(*SOLVER_EXECUTOR)
+            .collect()
+    }
+
+    async fn record(network: &'static EvmNetwork, log: Log) {
+        if log.removed == Some(true) {
+            return;
+        }
+        let tx_hash = log.transaction_hash.unwrap_or_default();
+        let event = match EscrowEvents::decode_log(&log.into()) {
+            Ok(event) => event,
+            Err(_) => return, // an escrow event the solver doesn't track
+        };
+        let ours = solver_addresses();
+
+        let mut intents = ESCROW_INTENTS.write().await;
+        intents.retain(|_, intent| intent.last_seen.elapsed() < RETENTION);
+
+        let intent_id = match &event {
+            EscrowEvents::IntentStoredFilter(e) => e.intent_id.clone(),
+            EscrowEvents::AuctionDataUpdatedFilter(e) => e.intent_id.clone(),
+            EscrowEvents::FundsSentToUserFilter(e) => e.intent_id.clone(),
+            EscrowEvents::CrossChainReleaseFilter(e) => e.intent_id.clone(),
+            EscrowEvents::IntentRefundedFilter(e) => e.intent_id.clone(),
+        };
+        let intent = intents
+            .entry((network.name, intent_id.clone()))
+            .or_insert_with(|| EscrowIntent {
+                stored: None,
+                auction: None,
+                funds_sent: None,
+                released: None,
+                refunded: None,
+                last_seen: Instant::now(),
+            });
+        intent.last_seen = Instant::now();
+
+        match event {
+            EscrowEvents::IntentStoredFilter(e) => {
+                println!(
+                    "Escrow on {}: intent {intent_id} stored, {} {:?} from {:?} ({:?})",
+                    network.name, e.amount_in, e.token_in, e.user, tx_hash
+                );
+                intent.stored = Some((e, tx_hash));
+            }
+            EscrowEvents::AuctionDataUpdatedFilter(e) => {
+                println!(
+                    "Escrow on {}: intent {intent_id} won by {} for {} ({:?})",
+                    network.name, e.winner_solver, e.amount_out, tx_hash
+                );
+                intent.auction = Some((e, tx_hash));
+            }
+            EscrowEvents::FundsSentToUserFilter(e) => {
+                if ours.contains(&e.solver) {
+                    println!(
+                        "Escrow on {}: settlement of intent {intent_id} confirmed, {} {:?} sent to {:?} ({:?})",
+                        network.name, e.amount_out, e.token_out, e.user, tx_hash
+                    );
+                } else {
+                    println!(
+                        "Escrow on {}: intent {intent_id} filled by another solver {:?} ({:?})",
+                        network.name, e.solver, tx_hash
+                    );
+                }
+                intent.funds_sent = Some((e, tx_hash));
+            }
+            EscrowEvents::CrossChainReleaseFilter(e) => {
+                if ours.contains(&e.solver) {
+                    println!(
+                        "Escrow on {}: received {} {:?} for intent {intent_id} ({:?})",
+                        network.name, e.amount_in, e.token_in, tx_hash
+                    );
+                }
+                intent.released = Some((e, tx_hash));
+            }
+            EscrowEvents::IntentRefundedFilter(e) => {
+                println!(
+                    "Escrow on {}: intent {intent_id} timed out, {} {:?} refunded to {:?} ({:?})",
+                    network.name, e.amount_in, e.token_in, e.user, tx_hash
+                );
+                intent.refunded = Some((e, tx_hash));
+            }
+        }
+    }
+
+    /// Fetches escrow logs from `from_block` to the latest block over HTTP,
+    /// `LOG_RANGE_BLOCKS` at a time, moving `from_block` past every range done.
+    async fn poll<M: Middleware>(
+        network: &'static EvmNetwork,
+        provider: &M,
+        filter: &Filter,
+        from_block: &mut U64,
+    ) -> Result<(), String> {
+        let latest = provider
+            .get_block_number()
+            .await
+            .map_err(|e| format!("Failed to get block number: {}", e))?;
+
+        while *from_block <= latest {
+            let to_block = latest.min(*from_block + LOG_RANGE_BLOCKS - 1);
+            let logs = provider
+                .get_logs(&filter.clone().from_block(*from_block).to_block(to_block))
+                .await
+                .map_err(|e| format!("Failed to get escrow logs: {}", e))?;
+            for log in logs {
+                record(network, log).await;
+            }
+            *from_block = to_block + 1;
+        }
+        Ok(())
+    }
+
+    /// Streams escrow logs over the websocket until the subscription drops.
+    /// New heads move `from_block` along, so falling back to polling doesn't
+    /// rescan blocks without escrow logs.
+    async fn subscribe(
+        network: &'static EvmNetwork,
+        ws_url: &str,
+        filter: &Filter,
+        from_block: &mut U64,
+    ) -> Result<(), String> {
+        let provider = Provider::<Ws>::connect(ws_url)
+            .await
+            .map_err(|e| format!("Failed to connect: {}", e))?;
+        let mut logs = provider
+            .subscribe_logs(filter)
+            .await
+            .map_err(|e| format!("Failed to subscribe: {}", e))?;
+        let mut heads = provider
+            .subscribe_blocks()
+            .await
+            .map_err(|e| format!("Failed to subscribe to new heads: {}", e))?;
+
+        // logs between the last poll and the subscription
+        let http = evm_context(network).await?.provider.clone();
+        poll(network, http.as_ref(), filter, from_block).await?;
+
+        loop {
+            tokio::select! {
+                log = logs.next() => match log {
+                    Some(log) => {
+                        if let Some(block) = log.block_number {
+                            *from_block = (*from_block).max(block + 1);
+                        }
+                        record(network, log).await;
+                    }
+                    None => break,
+                },
+                head = heads.next() => match head {
+                    // the head's own logs may still be on their way, so it is
+                    // fetched again should the websocket drop now
+                    Some(head) => {
+                        if let Some(block) = head.number {
+                            *from_block = (*from_block).max(block);
+                        }
+                    }
+                    None => break,
+                },
+            }
+        }
+        Err("subscription closed".to_string())
+    }
+
+    /// Follows the escrow of `network`: over `<NETWORK>_WS` when set, falling
+    /// back to HTTP polling whenever the websocket is down.
+    async fn listen(network: &'static EvmNetwork, escrow: Address) {
+        let filter = Filter::new().address(escrow);
+        let mut from_block = loop {
+            match evm_context(network).await {
+                Ok(context) => match context.provider.get_block_number().await {
+                    Ok(block) => break block.saturating_sub(U64::from(LOOKBACK_BLOCKS)),
+                    Err(e) => println!("Escrow listener on {}: {}", network.name, e),
+                },
+                Err(e) => println!("Escrow listener on {}: {}", network.name, e),
+            }
+            tokio::time::sleep(poll_interval()).await;
+        };
+
+        loop {
+            if let Some(ws_url) = network.ws_url() {
+                if let Err(e) = subscribe(network, &ws_url, &filter, &mut from_block).await {
+                    println!(
+                        "Escrow listener on {}: websocket {}, polling over HTTP",
+                        network.name, e
+                    );
+                }
+            }
+
+            let polled = match evm_context(network).await {
+                Ok(context) => {
+                    poll(network, context.provider.as_ref(), &filter, &mut from_block).await
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = polled {
+                println!("Escrow listener on {}: {}", network.name, e);
+            }
+            tokio::time::sleep(poll_interval()).await;
+        }
+    }
+
+    /// Starts a listener for every EVM network with an RPC and an escrow.
+    pub fn spawn_escrow_listeners() {
+        for network in EVM_NETWORKS {
+            if network.rpc_url().is_err() {
+                continue;
+            }
+            let escrow = match network
+                .escrow_address()
+                .and_then(|escrow| Address::from_str(&escrow).ok())
+            {
+                Some(escrow) => escrow,
+                None => continue,
+            };
+            tokio::spawn(listen(network, escrow));
+        }
+    }
+
+    /// Waits up to `timeout` for the events of `intent_id` on the escrow of
+    /// `network` to satisfy `seen`.
+    pub async fn wait_for_escrow_event(
+        network: &'static EvmNetwork,
+        intent_id: &str,
+        timeout: Duration,
+        seen: impl Fn(&EscrowIntent) -> bool,
+    ) -> Option<EscrowIntent> {
+        let key = (network.name, intent_id.to_string());
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(intent) = ESCROW_INTENTS.read().await.get(&key) {
+                if seen(intent) {
+                    return Some(intent.clone());
+                }
+            }
+            if Instant::now() >= deadline {
+                return None;
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    }
+
+    /// With `ESCROW_STORED_TIMEOUT` set, checks the user's funds are in the
+    /// escrow of an EVM source chain, waiting that many seconds for the
+    /// IntentStored event, before the solver pays anything out.
+    pub async fn ensure_escrowed(intent: &PostIntentInfo, intent_id: &str) -> Result<(), String> {
+        let timeout = match env::var("ESCROW_STORED_TIMEOUT")
+            .ok()
+            .and_then(|secs| secs.parse::<u64>().ok())
+        {
+            Some(secs) => Duration::from_secs(secs),
+            None => return Ok(()),
+        };
+        let network = match evm_network(&intent.src_chain) {
+            Some(network) => network,
+            None => return Ok(()),
+        };
+
+        // the user escrows on the source chain, an IntentStored elsewhere
+        // doesn't hold their funds
+        match wait_for_escrow_event(network, intent_id, timeout, |intent| {
+            intent.stored.is_some()
+        })
+        .await
+        {
+            Some(_) => Ok(()),
+            None => Err(format!(
+                "No IntentStored event for intent {intent_id} on {} after {}s",
+                intent.src_chain,
+                timeout.as_secs()
+            )),
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ethers::abi::{encode, Token};
+
+        fn log(block: u64, topics: Vec<H256>, data: Vec<Token>) -> Log {
+            Log {
+                block_number: Some(U64::from(block)),
+                transaction_hash: Some(TxHash::repeat_byte(block as u8)),
+                topics,
+                data: encode(&data).into(),
+                ..Default::default()
+            }
+        }
+
+        fn intent_stored(block: u64, intent_id: &str) -> Log {
+            log(
+                block,
+                vec![
+                    IntentStoredFilter::signature(),
+                    H256::from(Address::repeat_byte(0xaa)),
+                ],
+                vec![
+                    Token::String(intent_id.to_string()),
+                    Token::Address(Address::repeat_byte(0xbb)),
+                    Token::Uint(U256::from(5)),
+                ],
+            )
+        }
+
+        fn funds_sent(block: u64, intent_id: &str, solver: Address) -> Log {
+            log(
+                block,
+                vec![
+                    FundsSentToUserFilter::signature(),
+                    H256::from(solver),
+                    H256::from(Address::repeat_byte(0xaa)),
+                ],
+                vec![
+                    Token::String(intent_id.to_string()),
+                    Token::Address(Address::repeat_byte(0xcc)),
+                    Token::Uint(U256::from(1_000)),
+                ],
+            )
+        }
+
+        async fn seen(network: &'static EvmNetwork, intent_id: &str) -> Option<EscrowIntent> {
+            let key = (network.name, intent_id.to_string());
+            ESCROW_INTENTS.read().await.get(&key).cloned()
+        }
+
+        #[tokio::test]
+        async fn records_events_under_their_network() {
+            let (network, other) = (&EVM_NETWORKS[0], &EVM_NETWORKS[1]);
+            record(network, intent_stored(1, "network")).await;
+
+            assert!(seen(network, "network").await.unwrap().stored.is_some());
+            assert!(seen(other, "network").await.is_none());
+            let stored_elsewhere =
+                wait_for_escrow_event(other, "network", Duration::ZERO, |intent| {
+                    intent.stored.is_some()
+                })
+                .await;
+            assert!(stored_elsewhere.is_none());
+        }
+
+        #[tokio::test]
+        async fn ignores_removed_logs() {
+            let network = &EVM_NETWORKS[0];
+            let mut reorged = intent_stored(1, "removed");
+            reorged.removed = Some(true);
+            record(network, reorged).await;
+
+            assert!(seen(network, "removed").await.is_none());
+        }
+
+        #[tokio::test]
+        async fn tells_a_foreign_solver_fill_apart() {
+            let network = &EVM_NETWORKS[0];
+            record(
+                network,
+                funds_sent(1, "foreign", Address::repeat_byte(0xee)),
+            )
+            .await;
+            let ours = Address::from_str(SOLVER_ADDRESSES[0]).unwrap();
+            record(network, funds_sent(1, "ours", ours)).await;
+
+            let foreign = seen(network, "foreign").await.unwrap();
+            assert!(foreign.funds_sent.is_some());
+            assert!(!foreign.sent_by_solver());
+            assert!(seen(network, "ours").await.unwrap().sent_by_solver());
+        }
+
+        #[tokio::test]
+        async fn drops_intents_past_the_retention() {
+            let network = &EVM_NETWORKS[0];
+            record(network, intent_stored(1, "expired")).await;
+            {
+                let mut intents = ESCROW_INTENTS.write().await;
+                let intent = intents
+                    .get_mut(&(network.name, "expired".to_string()))
+                    .unwrap();
+                intent.last_seen = Instant::now() - RETENTION - Duration::from_secs(1);
+            }
+            record(network, intent_stored(2, "retained")).await;
+
+            assert!(seen(network, "expired").await.is_none());
+            assert!(seen(network, "retained").await.is_some());
+        }
+
+        #[tokio::test]
+        async fn polls_in_log_ranges_up_to_the_latest_block() {
+            let network = &EVM_NETWORKS[0];
+            let (provider, mock) = Provider::mocked();
+            // responses are served last pushed first
+            mock.push::<Vec<Log>, _>(vec![]).unwrap();
+            mock.push::<Vec<Log>, _>(vec![intent_stored(1_500, "polled")])
+                .unwrap();
+            mock.push::<Vec<Log>, _>(vec![]).unwrap();
+            mock.push(U64::from(2_499)).unwrap();
+
+            let filter = Filter::new().address(Address::repeat_byte(0xe5));
+            let mut from_block = U64::zero();
+            poll(network, &provider, &filter, &mut from_block)
+                .await
+                .unwrap();
+
+            assert_eq!(from_block, U64::from(2_500));
+            assert!(seen(network, "polled").await.unwrap().stored.is_some());
+            mock.assert_request("eth_blockNumber", ()).unwrap();
+            for (from, to) in [(0, 999), (1_000, 1_999), (2_000, 2_499)] {
+                let range = filter.clone().from_block(from).to_block(to);
+                mock.assert_request("eth_getLogs", [range]).unwrap();
+            }
+        }
+    }
+}
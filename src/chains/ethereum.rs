@@ -1,24 +1,33 @@
 pub mod ethereum_chain {
-    use crate::chains::get_token_info;
+    use crate::chains::bundle_relay::bundle_relay::send_bundle;
+    use crate::chains::executor::solver_executor::executor_fill;
+    use crate::chains::tx_manager::tx_manager::{send_and_confirm, TX_MANAGER_CONFIG};
+    use crate::chains::{
+        get_solver_address, get_token_info, registered_solver_addresses, SOLVER_EXECUTOR,
+    };
     use crate::chains::OperationOutput;
     use std::env;
     use crate::json;
-    use crate::routers::paraswap::paraswap_router::simulate_swap_paraswap;
-    use crate::routers::paraswap::paraswap_router::ParaswapParams;
+    use crate::routers::swap_router::{select_router, SwapParams, SwapSide, SwapTransaction};
+    use crate::routers::QuoteResult;
     use crate::OperationInput;
     use crate::PostIntentInfo;
     use crate::SOLVER_ADDRESSES;
     use crate::SOLVER_ID;
+    use ethers::abi::{encode, Token};
     use ethers::prelude::abigen;
     use ethers::prelude::*;
     use ethers::providers::{Http, Provider};
+    use ethers::types::transaction::eip2718::TypedTransaction;
+    use ethers::utils::keccak256;
+    use lazy_static::lazy_static;
     use num_bigint::BigInt;
-    use reqwest::Client;
+    use num_traits::ToPrimitive;
     // use serde::Deserialize;
+    use std::collections::HashMap;
     use std::str::FromStr;
     use std::sync::Arc;
-    use std::thread::sleep;
-    use std::time::Duration;
+    use tokio::sync::RwLock;
 
     abigen!(
         ERC20,
@@ -48,31 +57,23 @@ pub mod ethereum_chain {
             "name": "approve",
             "outputs": [{ "name": "", "type": "bool" }],
             "type": "function"
-        }]"#
-    );
-
-    abigen!(
-        Escrow,
-        r#"[{
-            "constant": false,
+        },
+        {
+            "constant": true,
             "inputs": [
-                {
-                    "components": [
-                        { "name": "intentId", "type": "string" },
-                        { "name": "solverOut", "type": "string" }
-                    ],
-                    "name": "solverTransferData",
-                    "type": "tuple"
-                }
+                { "name": "_owner", "type": "address" },
+                { "name": "_spender", "type": "address" }
             ],
-            "name": "sendFundsToUser",
-            "outputs": [],
-            "payable": true,
-            "stateMutability": "payable",
+            "name": "allowance",
+            "outputs": [{ "name": "", "type": "uint256" }],
             "type": "function"
         }]"#
     );
 
+    // bridge-escrow on EVM: intents escrowed by users, the auctioneer setting
+    // the winner, solvers paying out, and refunds once an intent times out
+    abigen!(Escrow, "abi/Escrow.json");
+
     abigen!(
         UsdtContract,
         r#"[
@@ -80,48 +81,223 @@ pub mod ethereum_chain {
         ]"#
     );
 
+    lazy_static! {
+        // <token, decimals>, token decimals never change so they are cached forever
+        static ref EVM_TOKEN_DECIMALS: Arc<RwLock<HashMap<Address, u8>>> =
+            Arc::new(RwLock::new(HashMap::new()));
+        // <chain id, context>, created on first use
+        static ref EVM_CONTEXTS: Arc<RwLock<HashMap<u64, Arc<EvmContext>>>> =
+            Arc::new(RwLock::new(HashMap::new()));
+    }
+
+    pub type EvmClient = SignerMiddleware<Provider<Http>, LocalWallet>;
+
+    /// Provider and `ETHEREUM_PKEY` signer of one EVM network, created once and
+    /// shared by every read and transaction on it, so they all use the same
+    /// connection pool, chain id, nonce manager and gas strategy.
+    #[derive(Debug)]
+    pub struct EvmContext {
+        pub provider: Arc<Provider<Http>>,
+        client: Option<Arc<EvmClient>>, // None without ETHEREUM_PKEY
+    }
+
+    impl EvmContext {
+        pub fn signer(&self) -> Result<Arc<EvmClient>, String> {
+            self.client
+                .clone()
+                .ok_or_else(|| "ETHEREUM_PKEY must be set".to_string())
+        }
+    }
+
+    pub async fn evm_context(network: &EvmNetwork) -> Result<Arc<EvmContext>, String> {
+        if let Some(context) = EVM_CONTEXTS.read().await.get(&network.chain_id) {
+            return Ok(context.clone());
+        }
+
+        let provider = Provider::<Http>::try_from(network.rpc_url()?)
+            .map_err(|e| format!("Failed to create {} provider: {}", network.name, e))?;
+        let client = match env::var("ETHEREUM_PKEY") {
+            Ok(private_key) => {
+                let wallet: LocalWallet = private_key
+                    .parse()
+                    .map_err(|e| format!("Failed to parse private key: {}", e))?;
+                Some(Arc::new(SignerMiddleware::new(
+                    provider.clone(),
+                    wallet.with_chain_id(network.chain_id),
+                )))
+            }
+            Err(_) => None,
+        };
+        let context = Arc::new(EvmContext {
+            provider: Arc::new(provider),
+            client,
+        });
+
+        let mut contexts = EVM_CONTEXTS.write().await;
+        Ok(contexts.entry(network.chain_id).or_insert(context).clone())
+    }
+
     pub const ESCROW_SC_ETHEREUM: &str = "0x3d34b4Ff589f9B97f8a5540feC1c2ABAB9D4C64c";
-    pub const PARASWAP: &str = "0x216b4b4ba9f3e719726886d34a177484278bfcae";
-
-    pub async fn fetch_eth_gas_price() -> Result<U256, Box<dyn std::error::Error>> {
-        let eth_rpc_url = env::var("ETHEREUM_RPC")
-            .expect("ETHEREUM_RPC environment variable not set");
-        let provider = Provider::<Http>::try_from(eth_rpc_url)
-            .map_err(|e| format!("Failed to create provider: {}", e))?;
-        let provider = Arc::new(provider);
-        let gas_price = provider.get_gas_price()
+
+    // gas limits of transactions that can't be estimated, e.g. later ones in a bundle
+    pub const SWAP_GAS_LIMIT: u64 = 500_000;
+    pub const APPROVE_GAS_LIMIT: u64 = 100_000;
+    pub const SEND_FUNDS_TO_USER_GAS_LIMIT: u64 = 300_000;
+
+    /// EVM network the solver can swap on. The escrow is only deployed on
+    /// Ethereum so far, L2 escrows are read from `escrow_env` once deployed.
+    #[derive(Debug)]
+    pub struct EvmNetwork {
+        pub name: &'static str,
+        pub chain_id: u64,
+        pub paraswap_network: u16, // `network` of the Paraswap API
+        pub rpc_env: &'static str,
+        pub ws_env: &'static str, // websocket RPC, escrow events are polled over HTTP without it
+        pub escrow_env: &'static str,
+        pub escrow: Option<&'static str>,
+        pub bundle_relay_env: &'static str, // eth_sendBundle endpoint, e.g. Flashbots
+        pub bridge_token: &'static str, // USDT
+        pub native_coingecko_id: &'static str, // prices gas in USD
+        pub priority_fee_per_gas: u128, // in wei
+    }
+
+    impl EvmNetwork {
+        pub fn rpc_url(&self) -> Result<String, String> {
+            env::var(self.rpc_env).map_err(|_| format!("{} must be set", self.rpc_env))
+        }
+
+        pub fn ws_url(&self) -> Option<String> {
+            env::var(self.ws_env).ok().filter(|url| !url.is_empty())
+        }
+
+        pub fn escrow_address(&self) -> Option<String> {
+            env::var(self.escrow_env)
+                .ok()
+                .or_else(|| self.escrow.map(|escrow| escrow.to_string()))
+        }
+
+        pub fn bundle_relay(&self) -> Option<String> {
+            env::var(self.bundle_relay_env)
+                .ok()
+                .filter(|relay| !relay.is_empty())
+        }
+    }
+
+    pub static EVM_NETWORKS: &[EvmNetwork] = &[
+        EvmNetwork {
+            name: "ethereum",
+            chain_id: 1,
+            paraswap_network: 1,
+            rpc_env: "ETHEREUM_RPC",
+            ws_env: "ETHEREUM_WS",
+            escrow_env: "ETHEREUM_ESCROW",
+            escrow: Some(ESCROW_SC_ETHEREUM),
+            bundle_relay_env: "ETHEREUM_BUNDLE_RELAY",
+            bridge_token: "0xdAC17F958D2ee523a2206206994597C13D831ec7",
+            native_coingecko_id: "ethereum",
+            priority_fee_per_gas: 500_000_000,
+        },
+        EvmNetwork {
+            name: "arbitrum",
+            chain_id: 42161,
+            paraswap_network: 42161,
+            rpc_env: "ARBITRUM_RPC",
+            ws_env: "ARBITRUM_WS",
+            escrow_env: "ARBITRUM_ESCROW",
+            escrow: None,
+            bundle_relay_env: "ARBITRUM_BUNDLE_RELAY",
+            bridge_token: "0xFd086bC7CD5C481DCC9C85ebE478A1C0b69FCbb9",
+            native_coingecko_id: "ethereum",
+            priority_fee_per_gas: 1_000_000,
+        },
+        EvmNetwork {
+            name: "optimism",
+            chain_id: 10,
+            paraswap_network: 10,
+            rpc_env: "OPTIMISM_RPC",
+            ws_env: "OPTIMISM_WS",
+            escrow_env: "OPTIMISM_ESCROW",
+            escrow: None,
+            bundle_relay_env: "OPTIMISM_BUNDLE_RELAY",
+            bridge_token: "0x94b008aA00579c1307B0EF2c499aD98a8ce58e58",
+            native_coingecko_id: "ethereum",
+            priority_fee_per_gas: 1_000_000,
+        },
+        EvmNetwork {
+            name: "base",
+            chain_id: 8453,
+            paraswap_network: 8453,
+            rpc_env: "BASE_RPC",
+            ws_env: "BASE_WS",
+            escrow_env: "BASE_ESCROW",
+            escrow: None,
+            bundle_relay_env: "BASE_BUNDLE_RELAY",
+            bridge_token: "0xfde4C96c8593536E31F229EA8f37b2ADa2699bb2",
+            native_coingecko_id: "ethereum",
+            priority_fee_per_gas: 1_000_000,
+        },
+        EvmNetwork {
+            name: "polygon",
+            chain_id: 137,
+            paraswap_network: 137,
+            rpc_env: "POLYGON_RPC",
+            ws_env: "POLYGON_WS",
+            escrow_env: "POLYGON_ESCROW",
+            escrow: None,
+            bundle_relay_env: "POLYGON_BUNDLE_RELAY",
+            bridge_token: "0xc2132D05D31c914a87C6611C10748AEb04B58e8F",
+            native_coingecko_id: "polygon-ecosystem-token",
+            priority_fee_per_gas: 30_000_000_000,
+        },
+    ];
+
+    pub fn evm_network(name: &str) -> Option<&'static EvmNetwork> {
+        EVM_NETWORKS.iter().find(|network| network.name == name)
+    }
+
+    pub async fn fetch_eth_gas_price(
+        network: &EvmNetwork,
+    ) -> Result<U256, Box<dyn std::error::Error>> {
+        let gas_price = evm_context(network)
+            .await?
+            .provider
+            .get_gas_price()
             .await
             .map_err(|e| format!("Failed to fetch gas price: {}", e))?;
         Ok(gas_price) // in wei
     }
 
-    pub async fn fetch_eip1559_fee() -> Result<(U256, U256), Box<dyn std::error::Error>> {
-        let eth_rpc_url = env::var("ETHEREUM_RPC")
-            .expect("ETHEREUM_RPC environment variable not set");
-        let provider = Provider::<Http>::try_from(eth_rpc_url)
-            .map_err(|e| format!("Failed to create provider: {}", e))?;
-        let provider = Arc::new(provider);
-        let (max_fee_per_gas, max_priority_fee_per_gas) = provider.estimate_eip1559_fees(None)
+    pub async fn ethereum_token_balance(
+        network: &EvmNetwork,
+        token_address: &str,
+        owner: &str,
+    ) -> Result<U256, Box<dyn std::error::Error>> {
+        let provider = evm_context(network).await?.provider.clone();
+
+        let token = UsdtContract::new(Address::from_str(token_address)?, provider);
+        let balance = token
+            .balance_of(Address::from_str(owner)?)
+            .call()
             .await
-            .map_err(|e| format!("Failed to fetch gas price: {}", e))?;
-        Ok((max_fee_per_gas, max_priority_fee_per_gas)) // in wei
+            .map_err(|e| format!("Failed to get token balance: {}", e))?;
+        Ok(balance)
     }
 
-    pub async fn handle_ethereum_execution(
+    pub async fn handle_evm_execution(
+        network: &EvmNetwork,
         intent: &PostIntentInfo,
         intent_id: &str,
         amount: &str,
+        quote: &QuoteResult,
     ) -> Result<(), String> {
-        let usdt_contract_address = "0xdac17f958d2ee523a2206206994597c13d831ec7";
+        let usdt_contract_address = network.bridge_token;
+        let escrow = network
+            .escrow_address()
+            .ok_or_else(|| format!("No escrow deployed on {}", network.name))?;
 
-        let rpc_url = env::var("ETHEREUM_RPC").expect("ETHEREUM_RPC must be set");
-        let private_key = env::var("ETHEREUM_PKEY").expect("ETHEREUM_PKEY must be set");
         let target_address: Address = Address::from_str(SOLVER_ADDRESSES.get(0).unwrap()).unwrap();
 
-        let provider = Arc::new(
-            Provider::<Http>::try_from(&rpc_url)
-                .map_err(|e| format!("Failed to create Ethereum provider: {}", e))?,
-        );
+        let provider = evm_context(network).await?.provider.clone();
 
         let usdt_contract = UsdtContract::new(
             Address::from_str(usdt_contract_address).unwrap(),
@@ -146,95 +322,93 @@ pub mod ethereum_chain {
             amount_in = transfer_input.amount_in.clone();
         }
 
-        // swap USDT -> token_out
-        if !token_out.eq_ignore_ascii_case(usdt_contract_address) {
-            if let Err(e) = ethereum_trasnfer_swap(intent_id, intent.clone(), amount).await {
+        if let Some(executor) = *SOLVER_EXECUTOR {
+            // swap USDT -> token_out, solver -> token_out -> user | user -> token_in -> solver
+            // and swap token_in -> USDT in one transaction, the executor being the
+            // registered winner the wallet can't pay itself
+            if let Err(e) =
+                executor_fill(network, executor, &escrow, intent, intent_id, amount, quote).await
+            {
                 return Err(format!(
-                    "Error occurred on Ethereum swap USDT -> token_out (solver must approve USDT to Paraswap 0x216b4b4ba9f3e719726886d34a177484278bfcae first): {}",
-                    e
+                    "Error occurred on {} executor fill (solver must approve USDT to the executor {:?} first): {}",
+                    network.name, executor, e
                 ));
             }
-
-            if let Err(e) = approve_erc20(
-                &rpc_url,
-                &private_key,
-                &token_out,
-                ESCROW_SC_ETHEREUM,
-                amount,
-            )
-            .await
-            {
-                println!("Error approving {token_out} for solver: {e}");
-                return Err(e.to_string());
-            }
-        }
-
-        let solver_out = if intent.src_chain == "ethereum" {
-            SOLVER_ADDRESSES.get(0).unwrap()
-        } else if intent.src_chain == "solana" {
-            SOLVER_ADDRESSES.get(1).unwrap()
-        } else {
-            panic!("chain not supported, this should't happen");
-        };
-
-        // solver -> token_out -> user | user -> token_in -> solver
-        if let Err(e) = ethereum_send_funds_to_user(
-            &rpc_url,
-            &private_key,
-            ESCROW_SC_ETHEREUM,
-            intent_id,
-            solver_out,
-            U256::zero(),
-        )
-        .await
-        {
-            println!("Error occurred on Ethereum send token_out -> user & user sends token_in -> solver (solver must approve USDT to Escrow 0xA7C369Afd19E9866674B1704a520f42bC8958573 first): {}", e);
-            return Err(e.to_string());
-        // swap token_in -> USDT
-        } else if intent.src_chain == intent.dst_chain
-            && !token_in.eq_ignore_ascii_case(usdt_contract_address)
+        } else if let (Some(relay_url), "swap") =
+            (network.bundle_relay(), intent.function_name.as_str())
         {
+            // same steps as below, landing together or not at all, off the public mempool
             if let Err(e) =
-                approve_erc20(&rpc_url, &private_key, &token_in, PARASWAP, &amount_in).await
+                ethereum_bundle_fill(network, &relay_url, &escrow, intent, intent_id, amount, quote)
+                    .await
             {
-                println!("Error approving {token_in} for solver: {e}");
-                return Err(e.to_string());
+                return Err(format!(
+                    "Error occurred on {} bundle fill (solver must approve USDT to the router allowance target first): {}",
+                    network.name, e
+                ));
             }
-
-            let (token_out, token1_decimals) = match get_token_info("USDT", "ethereum") {
-                Some((token_out, token1_decimals)) => (token_out.to_string(), token1_decimals),
-                None => {
-                    println!("Failed to get token info for USDT on Ethereum");
-                    return Err("Failed to get token info".to_string());
+        } else {
+            // swap USDT -> token_out
+            if !token_out.eq_ignore_ascii_case(usdt_contract_address) {
+                if let Err(e) =
+                    ethereum_trasnfer_swap(network, intent_id, intent.clone(), amount, quote).await
+                {
+                    return Err(format!(
+                        "Error occurred on {} swap USDT -> token_out (solver must approve USDT to the router allowance target first): {}",
+                        network.name, e
+                    ));
                 }
-            };
 
-            let token0_decimals = get_evm_token_decimals(&ERC20::new(
-                Address::from_str(&token_in).unwrap(),
-                provider.clone(),
-            ))
-            .await;
-
-            let paraswap_params = ParaswapParams {
-                side: "SELL".to_string(),
-                chain_id: 1,
-                amount_in: BigInt::from_str(&amount_in).unwrap(),
-                token_in: Address::from_str(&token_in).unwrap(),
-                token_out: Address::from_str(&token_out).unwrap(),
-                token0_decimals: token0_decimals as u32,
-                token1_decimals: token1_decimals as u32,
-                wallet_address: Address::from_str(SOLVER_ADDRESSES.get(0).unwrap()).unwrap(),
-                receiver_address: Address::from_str(SOLVER_ADDRESSES.get(0).unwrap()).unwrap(),
-                client_aggregator: Client::new(),
-            };
+                if let Err(e) = approve_erc20(network, &token_out, &escrow, amount).await
+                {
+                    println!("Error approving {token_out} for solver: {e}");
+                    return Err(e.to_string());
+                }
+            }
 
-            let (_res_amount, res_data, res_to) = simulate_swap_paraswap(paraswap_params)
-                .await
-                .map_err(|e| format!("Error simulating Paraswap swap: {}", e))?;
+            let solver_out = get_solver_address(&intent.src_chain)
+                .expect("chain not supported, this should't happen");
 
-            if let Err(e) = send_tx(res_to, res_data, 1, 500_000, 0, rpc_url).await {
-                println!("Error sending transaction on Ethereum: {}", e);
+            // solver -> token_out -> user | user -> token_in -> solver
+            if let Err(e) = ethereum_send_funds_to_user(
+                network,
+                &escrow,
+                intent_id,
+                solver_out,
+                U256::zero(),
+            )
+            .await
+            {
+                println!("Error occurred on {} send token_out -> user & user sends token_in -> solver (solver must approve USDT to Escrow {} first): {}", network.name, escrow, e);
                 return Err(e.to_string());
+            // swap token_in -> USDT
+            } else if intent.src_chain == intent.dst_chain
+                && !token_in.eq_ignore_ascii_case(usdt_contract_address)
+            {
+                let token_out = match get_token_info("USDT", network.name) {
+                    Some((token_out, _)) => token_out.to_string(),
+                    None => {
+                        println!("Failed to get token info for USDT on {}", network.name);
+                        return Err("Failed to get token info".to_string());
+                    }
+                };
+
+                let swap_params = SwapParams {
+                    token_in: token_in.clone(),
+                    token_out,
+                    amount: BigInt::from_str(&amount_in).unwrap(),
+                    side: SwapSide::ExactIn,
+                    sender: SOLVER_ADDRESSES.get(0).unwrap().to_string(),
+                    receiver: SOLVER_ADDRESSES.get(0).unwrap().to_string(),
+                    slippage_bps: 100,
+                };
+
+                if let Err(e) =
+                    ethereum_swap(network, &swap_params, quote.src_router, true, None).await
+                {
+                    println!("Error sending transaction on {}: {}", network.name, e);
+                    return Err(e.to_string());
+                }
             }
         }
 
@@ -252,13 +426,14 @@ pub mod ethereum_chain {
             };
 
             println!(
-                "You have {} {} USDT on intent {intent_id}",
+                "You have {} {} USDT on intent {intent_id} (quoted commission {} USDT)",
                 if balance_post >= balance_ant {
                     "won"
                 } else {
                     "lost"
                 },
-                balance.as_u128() as f64 / 1e6
+                balance.as_u128() as f64 / 1e6,
+                quote.commission.to_f64().unwrap_or_default() / 1e6
             );
         }
 
@@ -266,12 +441,12 @@ pub mod ethereum_chain {
     }
 
     pub async fn ethereum_trasnfer_swap(
+        network: &EvmNetwork,
         intent_id: &str,
         intent: PostIntentInfo,
         amount: &str,
+        quote: &QuoteResult,
     ) -> Result<(), String> {
-        let client_rpc =
-            env::var("ETHEREUM_RPC").map_err(|e| format!("ETHEREUM_RPC must be set: {}", e))?;
         let mut token_out = String::default();
 
         match intent.function_name.as_str() {
@@ -281,9 +456,7 @@ pub mod ethereum_chain {
                 }
 
                 match transfer_erc20(
-                    &client_rpc,
-                    &env::var("ETHEREUM_PKEY")
-                        .map_err(|e| format!("ETHEREUM_PKEY must be set: {}", e))?,
+                    network,
                     &token_out,
                     SOLVER_ADDRESSES.get(0).unwrap(),
                     &amount.to_string(),
@@ -315,47 +488,34 @@ pub mod ethereum_chain {
                 }
             }
             "swap" => {
-                let (token_in, token0_decimals) = get_token_info("USDT", "ethereum")
+                let (token_in, _) = get_token_info("USDT", network.name)
                     .ok_or_else(|| "Failed to get token info".to_string())?;
 
                 if let OperationOutput::SwapTransfer(transfer_output) = &intent.outputs {
                     token_out = transfer_output.token_out.clone();
                 }
 
-                let provider = Provider::<Http>::try_from(client_rpc.replace("wss", "https"))
-                    .map_err(|e| format!("Failed to create provider: {}", e))?;
-                let provider = Arc::new(provider);
-
-                let token1_decimals = get_evm_token_decimals(&ERC20::new(
-                    Address::from_str(&token_out)
-                        .map_err(|e| format!("Invalid token_out address: {}", e))?,
-                    provider.clone(),
-                ))
-                .await;
-
-                let paraswap_params = ParaswapParams {
-                    side: "BUY".to_string(),
-                    chain_id: 1,
-                    amount_in: BigInt::from_str(amount)
+                let swap_params = SwapParams {
+                    token_in: token_in.to_string(),
+                    token_out,
+                    amount: BigInt::from_str(amount)
                         .map_err(|e| format!("Invalid amount: {}", e))?,
-                    token_in: Address::from_str(&token_in)
-                        .map_err(|e| format!("Invalid token_in address: {}", e))?,
-                    token_out: Address::from_str(&token_out)
-                        .map_err(|e| format!("Invalid token_out address: {}", e))?,
-                    token0_decimals: token0_decimals as u32,
-                    token1_decimals: token1_decimals as u32,
-                    wallet_address: Address::from_str(SOLVER_ADDRESSES.get(0).unwrap())
-                        .map_err(|e| format!("Invalid wallet address: {}", e))?,
-                    receiver_address: Address::from_str(SOLVER_ADDRESSES.get(0).unwrap())
-                        .map_err(|e| format!("Invalid receiver address: {}", e))?,
-                    client_aggregator: Client::new(),
+                    side: SwapSide::ExactOut,
+                    sender: SOLVER_ADDRESSES.get(0).unwrap().to_string(),
+                    receiver: SOLVER_ADDRESSES.get(0).unwrap().to_string(),
+                    slippage_bps: 100,
                 };
 
-                let (_res_amount, res_data, res_to) = simulate_swap_paraswap(paraswap_params)
-                    .await
-                    .map_err(|e| format!("Failed to simulate swap: {}", e))?;
-
-                let tx_hash = send_tx(res_to, res_data, 1, 500_000, 0, client_rpc).await;
+                // USDT is approved once to the router allowance targets, see README
+                // never spend more USDT than the bid budgeted for the swap
+                let tx_hash = ethereum_swap(
+                    network,
+                    &swap_params,
+                    quote.dst_router,
+                    false,
+                    Some(&quote.bridge_amount),
+                )
+                .await;
 
                 // since tx_hash is a String, handle error separately if needed
                 if tx_hash.is_err() {
@@ -372,18 +532,12 @@ pub mod ethereum_chain {
     }
 
     async fn transfer_erc20(
-        provider_url: &str,
-        private_key: &str,
+        network: &EvmNetwork,
         token_address: &str,
         recipient_address: &str,
         amount: &str,
     ) -> Result<TxHash, Box<dyn std::error::Error>> {
-        let provider = Provider::<Http>::try_from(provider_url)?;
-        let provider = Arc::new(provider);
-
-        let wallet: LocalWallet = private_key.parse()?;
-        let wallet = wallet.with_chain_id(1u64); // Mainnet
-        let wallet = Arc::new(SignerMiddleware::new(provider.clone(), wallet));
+        let wallet = evm_context(network).await?.signer()?;
 
         let token_address = token_address.parse::<Address>()?;
         let erc20 = ERC20::new(token_address, wallet.clone());
@@ -392,95 +546,46 @@ pub mod ethereum_chain {
         let amount = U256::from_dec_str(amount).unwrap();
 
         let tx = erc20.transfer(recipient, amount);
-        let tx = tx.send().await?;
+        let receipt = send_and_confirm(&wallet, tx.tx, &TX_MANAGER_CONFIG).await?;
 
-        Ok(tx.tx_hash())
+        Ok(receipt.transaction_hash)
     }
 
     pub async fn send_tx(
+        network: &EvmNetwork,
         to: Address,
-        data: String,
-        chain_id: u64,
+        data: Bytes,
         gas: u64,
-        value: u128,
-        url: String,
+        value: U256,
     ) -> Result<(), String> {
-        let prvk = match env::var("ETHEREUM_PKEY") {
-            Ok(key) => match secp256k1::SecretKey::from_str(&key) {
-                Ok(prvk) => prvk,
-                Err(e) => return Err(format!("Failed to parse private key: {}", e)),
-            },
-            Err(_) => return Err("ETHEREUM_PKEY environment variable is not set".to_string()),
-        };
+        let client = evm_context(network).await?.signer()?;
 
-        // Get gas
-        // let base_fee_per_gas = fetch_eth_gas_price()
-        //     .await
-        //     .map_err(|e| format!("Failed to fetch gas price: {}", e))?;
-
-        // let priority_fee_per_gas: u128 = 2_000_000_000; // This is already in wei
-        // let max_fee_per_gas = base_fee_per_gas + priority_fee_per_gas;
-
-        let (max_fee_per_gas, max_priority_fee_per_gas) = fetch_eip1559_fee().await.unwrap();
-        // EIP-1559 transaction
-        let tx_object = web3::types::TransactionParameters {
-            to: Some(to),
-            gas: U256::from(gas),
-            value: U256::from(value),
-            data: web3::types::Bytes::from(
-                hex::decode(&data[2..]).map_err(|e| format!("Failed to decode data: {}", e))?,
-            ),
-            chain_id: Some(chain_id),
-            transaction_type: Some(web3::types::U64::from(2)),
-            access_list: None,
-            max_fee_per_gas: Some(U256::from(max_fee_per_gas)),
-            max_priority_fee_per_gas: Some(U256::from(max_priority_fee_per_gas)),
-            ..Default::default()
-        };
+        // EIP-1559 transaction, priced and re-priced by the tx manager
+        let tx = Eip1559TransactionRequest::new()
+            .to(to)
+            .gas(gas)
+            .value(value)
+            .data(data);
 
-        let web3_query = web3::Web3::new(
-            web3::transports::Http::new(&url)
-                .map_err(|e| format!("Failed to create HTTP transport: {}", e))?,
-        );
-
-        let signed = web3_query
-            .accounts()
-            .sign_transaction(tx_object, &prvk)
+        send_and_confirm(&client, tx.into(), &TX_MANAGER_CONFIG)
             .await
-            .map_err(|e| format!("Failed to sign transaction: {}", e))?;
+            .map_err(|e| e.to_string())?;
 
-        let tx_hash = web3_query
-            .eth()
-            .send_raw_transaction(signed.raw_transaction)
-            .await
-            .map_err(|e| format!("Failed to send transaction: {}", e))?;
-
-        // println!("Transaction hash: {:?}", tx_hash);
-
-        // Poll for the transaction receipt
-        loop {
-            match web3_query.eth().transaction_receipt(tx_hash).await {
-                Ok(Some(receipt)) => {
-                    if receipt.status == Some(U64::from(1)) {
-                        // println!("Transaction confirmed: {:?}", receipt);
-                        return Ok(());
-                    } else {
-                        return Err("Transaction failed".to_string());
-                    }
-                }
-                Ok(None) => {
-                    // Receipt is not yet available, continue polling
-                    //println!("Transaction pending...");
-                    sleep(Duration::from_secs(5));
-                }
-                Err(e) => return Err(format!("Error while fetching transaction receipt: {}", e)),
-            }
-        }
+        Ok(())
     }
 
     pub async fn get_evm_token_decimals(erc20: &ERC20<Provider<Http>>) -> u8 {
+        if let Some(decimals) = EVM_TOKEN_DECIMALS.read().await.get(&erc20.address()) {
+            return *decimals;
+        }
+
         match erc20.decimals().call().await {
-            Ok(decimals) => decimals,
+            Ok(decimals) => {
+                let mut cache = EVM_TOKEN_DECIMALS.write().await;
+                cache.insert(erc20.address(), decimals);
+                drop(cache);
+                decimals
+            }
             Err(e) => {
                 eprintln!("Error getting decimals: {}", e);
                 0
@@ -488,87 +593,408 @@ pub mod ethereum_chain {
         }
     }
 
-    pub async fn ethereum_simulate_swap(
-        token_in: &str,
-        amount_in: &str,
-        token_out: &str,
-    ) -> BigInt {
-        let rpc_url = env::var("ETHEREUM_RPC").expect("ETHEREUM_RPC must be set");
-        let provider = Provider::<Http>::try_from(rpc_url)
-            .map_err(|e| e.to_string())
-            .unwrap();
-        let provider = Arc::new(provider);
-        let token_in = Address::from_str(token_in).unwrap();
-        let token_out = Address::from_str(token_out).unwrap();
-        let token0_decimals = get_evm_token_decimals(&ERC20::new(token_in, provider.clone())).await;
-        let token1_decimals =
-            get_evm_token_decimals(&ERC20::new(token_out, provider.clone())).await;
-
-        let paraswap_params = ParaswapParams {
-            side: "SELL".to_string(),
-            chain_id: 1,
-            amount_in: BigInt::from_str(amount_in).unwrap(),
-            token_in: token_in,
-            token_out: token_out,
-            token0_decimals: token0_decimals as u32,
-            token1_decimals: token1_decimals as u32,
-            wallet_address: Address::from_str(SOLVER_ADDRESSES.get(0).unwrap()).unwrap(),
-            receiver_address: Address::from_str(SOLVER_ADDRESSES.get(0).unwrap()).unwrap(),
-            client_aggregator: Client::new(),
+    /// Swap transaction built by a router, see `SwapTransaction::Evm`.
+    pub struct EvmSwap {
+        pub to: Address,
+        pub data: Bytes,
+        pub value: U256,
+        pub allowance_target: Address,
+        pub amount_in: BigInt,
+    }
+
+    impl EvmSwap {
+        pub fn transaction(&self, gas: u64) -> TypedTransaction {
+            Eip1559TransactionRequest::new()
+                .to(self.to)
+                .data(self.data.clone())
+                .value(self.value)
+                .gas(gas)
+                .into()
+        }
+    }
+
+    /// Builds a swap through `router`, failing when the router expects to
+    /// spend more than `max_amount_in`.
+    pub async fn build_evm_swap(
+        network: &EvmNetwork,
+        params: &SwapParams,
+        router: Option<&str>,
+        max_amount_in: Option<&BigInt>,
+    ) -> Result<EvmSwap, String> {
+        let router = select_router(network.name, router)?;
+
+        let (to, data, value, allowance_target, amount_in) =
+            match router.build_swap(params).await? {
+                SwapTransaction::Evm {
+                    to,
+                    data,
+                    value,
+                    allowance_target,
+                    amount_in,
+                } => (to, data, value, allowance_target, amount_in),
+                _ => {
+                    return Err(format!(
+                        "{} did not build an EVM transaction",
+                        router.name()
+                    ))
+                }
+            };
+
+        if let Some(max_amount_in) = max_amount_in {
+            if &amount_in > max_amount_in {
+                return Err(format!(
+                    "{} expects to spend {} token_in, above the {} budgeted",
+                    router.name(),
+                    amount_in,
+                    max_amount_in
+                ));
+            }
+        }
+
+        Ok(EvmSwap {
+            to,
+            data: Bytes::from_str(&data).map_err(|e| format!("Failed to decode data: {}", e))?,
+            value,
+            allowance_target,
+            amount_in,
+        })
+    }
+
+    /// Swaps through `router`, approving token_in to the router's allowance
+    /// target first when `approve_token_in` is set (ExactIn only). Aborts
+    /// before sending when the router expects to spend more than `max_amount_in`.
+    pub async fn ethereum_swap(
+        network: &EvmNetwork,
+        params: &SwapParams,
+        router: Option<&str>,
+        approve_token_in: bool,
+        max_amount_in: Option<&BigInt>,
+    ) -> Result<(), String> {
+        let swap = build_evm_swap(network, params, router, max_amount_in).await?;
+
+        if approve_token_in {
+            approve_erc20(
+                network,
+                &params.token_in,
+                &format!("{:?}", swap.allowance_target),
+                &params.amount.to_string(),
+            )
+            .await?;
+        }
+
+        send_tx(network, swap.to, swap.data, SWAP_GAS_LIMIT, swap.value).await
+    }
+
+    fn with_gas(mut tx: TypedTransaction, gas: u64) -> TypedTransaction {
+        tx.set_gas(gas);
+        tx
+    }
+
+    /// Fills a "swap" intent with the sequential path's transactions (USDT ->
+    /// token_out swap, approve to escrow, sendFundsToUser, approve and swap
+    /// token_in -> USDT) submitted as one bundle to `relay_url`.
+    async fn ethereum_bundle_fill(
+        network: &EvmNetwork,
+        relay_url: &str,
+        escrow: &str,
+        intent: &PostIntentInfo,
+        intent_id: &str,
+        amount: &str,
+        quote: &QuoteResult,
+    ) -> Result<(), String> {
+        let mut token_in = String::default();
+        let mut token_out = String::default();
+        let mut amount_in = String::default();
+
+        if let OperationOutput::SwapTransfer(transfer_output) = &intent.outputs {
+            token_out = transfer_output.token_out.clone();
+        }
+        if let OperationInput::SwapTransfer(transfer_input) = &intent.inputs {
+            token_in = transfer_input.token_in.clone();
+            amount_in = transfer_input.amount_in.clone();
+        }
+
+        let client = evm_context(network).await?.signer()?;
+        let solver = format!("{:?}", client.address());
+        let escrow = Address::from_str(escrow).map_err(|e| format!("Invalid escrow: {}", e))?;
+        let amount = BigInt::from_str(amount).map_err(|e| format!("Invalid amount: {}", e))?;
+        let to_u256 = |amount: &BigInt| {
+            U256::from_dec_str(&amount.to_string()).map_err(|e| format!("Invalid amount: {}", e))
         };
+        let mut txs = Vec::new();
+
+        // swap USDT -> token_out, USDT is approved once to the router allowance targets
+        if !token_out.eq_ignore_ascii_case(network.bridge_token) {
+            let swap_params = SwapParams {
+                token_in: network.bridge_token.to_string(),
+                token_out: token_out.clone(),
+                amount: amount.clone(),
+                side: SwapSide::ExactOut,
+                sender: solver.clone(),
+                receiver: solver.clone(),
+                slippage_bps: 100,
+            };
+            let swap = build_evm_swap(
+                network,
+                &swap_params,
+                quote.dst_router,
+                Some(&quote.bridge_amount),
+            )
+            .await?;
+            txs.push(swap.transaction(SWAP_GAS_LIMIT));
+
+            let token_out = Address::from_str(&token_out)
+                .map_err(|e| format!("Invalid token_out: {}", e))?;
+            let erc20 = ERC20::new(token_out, client.clone());
+            txs.extend(
+                approve_transactions(
+                    &erc20,
+                    escrow,
+                    to_u256(&amount)?,
+                    ApprovalPolicy::from_env(),
+                )
+                .await?,
+            );
+        }
+
+        // solver -> token_out -> user | user -> token_in -> solver
+        let solver_out = get_solver_address(&intent.src_chain)
+            .ok_or_else(|| format!("No solver address for {}", intent.src_chain))?;
+        let send_funds = Escrow::new(escrow, client.clone())
+            .send_funds_to_user(SolverTransferData {
+                intent_id: intent_id.to_string(),
+                solver_out: solver_out.to_string(),
+            });
+        txs.push(with_gas(send_funds.tx, SEND_FUNDS_TO_USER_GAS_LIMIT));
+
+        // swap token_in -> USDT
+        if intent.src_chain == intent.dst_chain
+            && !token_in.eq_ignore_ascii_case(network.bridge_token)
+        {
+            let swap_params = SwapParams {
+                token_in: token_in.clone(),
+                token_out: network.bridge_token.to_string(),
+                amount: BigInt::from_str(&amount_in)
+                    .map_err(|e| format!("Invalid amount_in: {}", e))?,
+                side: SwapSide::ExactIn,
+                sender: solver.clone(),
+                receiver: solver.clone(),
+                slippage_bps: 100,
+            };
+            let swap = build_evm_swap(network, &swap_params, quote.src_router, None).await?;
+
+            let token_in = Address::from_str(&token_in)
+                .map_err(|e| format!("Invalid token_in: {}", e))?;
+            let erc20 = ERC20::new(token_in, client.clone());
+            txs.extend(
+                approve_transactions(
+                    &erc20,
+                    swap.allowance_target,
+                    to_u256(&swap_params.amount)?,
+                    ApprovalPolicy::from_env(),
+                )
+                .await?,
+            );
+            txs.push(swap.transaction(SWAP_GAS_LIMIT));
+        }
+
+        send_bundle(&client, relay_url, txs)
+            .await
+            .map_err(|e| e.to_string())?;
 
-        let (_res_amount, _, _) = simulate_swap_paraswap(paraswap_params).await.unwrap();
-        _res_amount
+        Ok(())
     }
 
     pub async fn ethereum_send_funds_to_user(
-        provider_url: &str,
-        private_key: &str,
+        network: &EvmNetwork,
         contract_address: &str,
         intent_id: &str,
         solver_out: &str,
         value_in_wei: U256,
-    ) -> Result<TransactionReceipt, Box<dyn std::error::Error>> {
-        let provider = Provider::<Http>::try_from(provider_url)?;
-        let provider = Arc::new(provider);
-
-        let wallet: LocalWallet = private_key.parse()?;
-        let wallet = wallet.with_chain_id(1u64); // Mainnet
-        let wallet = Arc::new(SignerMiddleware::new(provider.clone(), wallet));
+    ) -> Result<TransactionReceipt, Box<dyn std::error::Error + Send + Sync>> {
+        let wallet = evm_context(network).await?.signer()?;
 
         let contract_address = contract_address.parse::<Address>()?;
         let contract = Escrow::new(contract_address, wallet.clone());
 
-        let solver_transfer_data = (intent_id.to_string(), solver_out.to_string());
+        let solver_transfer_data = SolverTransferData {
+            intent_id: intent_id.to_string(),
+            solver_out: solver_out.to_string(),
+        };
 
         let contract = contract
             .send_funds_to_user(solver_transfer_data)
             .value(value_in_wei);
-        let pending_tx = contract.send().await?;
-
-        let tx_receipt = pending_tx
-            .await?
-            .expect("Failed to fetch transaction receipt");
+        let tx_receipt = send_and_confirm(&wallet, contract.tx, &TX_MANAGER_CONFIG).await?;
 
         Ok(tx_receipt)
     }
 
+    /// Unless `ESCROW_VERIFY=false`, reads the intent from the escrow of its
+    /// EVM destination chain, where the auctioneer records the winner, and
+    /// checks before anything is paid that this solver won it for at most the
+    /// winning bid. On single domain intents the same escrow also holds
+    /// token_in, so the timeout and escrowed amounts are checked too. Intents
+    /// paid out on other chains, or without an escrow address, skip the check.
+    pub async fn verify_escrow_intent(
+        intent: &PostIntentInfo,
+        intent_id: &str,
+        amount: &str,
+    ) -> Result<(), String> {
+        if env::var("ESCROW_VERIFY").as_deref() == Ok("false") {
+            return Ok(());
+        }
+        let network = match evm_network(&intent.dst_chain) {
+            Some(network) => network,
+            None => return Ok(()),
+        };
+        let escrow = match network.escrow_address() {
+            Some(escrow) => {
+                Address::from_str(&escrow).map_err(|e| format!("Invalid escrow: {}", e))?
+            }
+            None => return Ok(()),
+        };
+
+        let provider = evm_context(network).await?.provider.clone();
+        let escrow = Escrow::new(escrow, provider);
+        check_escrow_intent(&escrow, network, intent, intent_id, amount).await
+    }
+
+    /// Reads `intent_id` with the escrow's `getIntentInfo` and checks it
+    /// against the won intent, see `verify_escrow_intent`.
+    async fn check_escrow_intent<M: Middleware>(
+        escrow: &Escrow<M>,
+        network: &EvmNetwork,
+        intent: &PostIntentInfo,
+        intent_id: &str,
+        amount: &str,
+    ) -> Result<(), String> {
+        let info = escrow
+            .get_intent_info(intent_id.to_string())
+            .call()
+            .await
+            .map_err(|e| {
+                format!("Failed to read intent {intent_id} from the {} escrow: {}", network.name, e)
+            })?;
+
+        // EVM addresses may differ in checksum casing, Solana ones may not
+        let same_address = |a: &str, b: &str| {
+            if a.starts_with("0x") {
+                a.eq_ignore_ascii_case(b)
+            } else {
+                a == b
+            }
+        };
+        // the executor wins instead of the wallet when SOLVER_EXECUTOR is set
+        let won = registered_solver_addresses()
+            .iter()
+            .any(|solver| same_address(solver, &info.winner_solver));
+        if !won {
+            return Err(format!(
+                "Intent {intent_id} was won by {:?} on chain, not this solver",
+                info.winner_solver
+            ));
+        }
+
+        let amount = U256::from_dec_str(amount).map_err(|e| format!("Invalid amount: {}", e))?;
+        let amount_out = U256::from_dec_str(&info.amount_out)
+            .map_err(|e| format!("Invalid escrow amount_out: {}", e))?;
+        if amount < amount_out {
+            return Err(format!(
+                "Intent {intent_id} requires {} token_out on chain, winning bid was {}",
+                amount_out, amount
+            ));
+        }
+
+        // token_in is escrowed on the source chain, only readable here on single domain
+        if intent.src_chain != intent.dst_chain {
+            return Ok(());
+        }
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|e| format!("Invalid system time: {}", e))?
+            .as_secs();
+        if info.timeout <= U256::from(now) {
+            return Err(format!("Intent {intent_id} timed out at {}", info.timeout));
+        }
+
+        if let OperationInput::SwapTransfer(transfer_input) = &intent.inputs {
+            if !same_address(&format!("{:?}", info.token_in), &transfer_input.token_in) {
+                return Err(format!(
+                    "Intent {intent_id} escrowed {:?}, not {}",
+                    info.token_in, transfer_input.token_in
+                ));
+            }
+            let amount_in = U256::from_dec_str(&transfer_input.amount_in)
+                .map_err(|e| format!("Invalid amount_in: {}", e))?;
+            if info.amount_in < amount_in {
+                return Err(format!(
+                    "Intent {intent_id} escrowed {} token_in, {} expected",
+                    info.amount_in, amount_in
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// How much is approved when an allowance is too low, `APPROVAL_POLICY`:
+    /// `exact` (default) approves the amount needed, `max` approves
+    /// `U256::MAX` once so later intents skip the approve.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum ApprovalPolicy {
+        Exact,
+        Max,
+    }
+
+    impl ApprovalPolicy {
+        pub fn from_env() -> Self {
+            match env::var("APPROVAL_POLICY").as_deref() {
+                Ok("max") => ApprovalPolicy::Max,
+                _ => ApprovalPolicy::Exact,
+            }
+        }
+    }
+
+    /// Approve transactions needed for `spender` to pull `amount` of the
+    /// token: none when the allowance already covers it, else an approve
+    /// preceded by a reset to zero, as USDT rejects changing a non-zero allowance.
+    pub async fn approve_transactions(
+        erc20: &ERC20<EvmClient>,
+        spender: Address,
+        amount: U256,
+        policy: ApprovalPolicy,
+    ) -> Result<Vec<TypedTransaction>, String> {
+        let owner = erc20.client().address();
+        let allowance = erc20
+            .allowance(owner, spender)
+            .call()
+            .await
+            .map_err(|e| format!("Failed to get allowance: {}", e))?;
+        if allowance >= amount {
+            return Ok(Vec::new());
+        }
+
+        let mut txs = Vec::with_capacity(2);
+        if !allowance.is_zero() {
+            txs.push(with_gas(erc20.approve(spender, U256::zero()).tx, APPROVE_GAS_LIMIT));
+        }
+        let approve_amount = match policy {
+            ApprovalPolicy::Exact => amount,
+            ApprovalPolicy::Max => U256::MAX,
+        };
+        txs.push(with_gas(erc20.approve(spender, approve_amount).tx, APPROVE_GAS_LIMIT));
+        Ok(txs)
+    }
+
     pub async fn approve_erc20(
-        provider_url: &str,
-        private_key: &str,
+        network: &EvmNetwork,
         token_address: &str,
         spender_address: &str,
         amount: &str,
     ) -> Result<(), String> {
-        let provider = Provider::<Http>::try_from(provider_url)
-            .map_err(|e| format!("Failed to create provider: {}", e))?;
-        let provider = Arc::new(provider);
-
-        let wallet: LocalWallet = private_key
-            .parse()
-            .map_err(|e| format!("Failed to parse private key: {}", e))?;
-        let wallet = wallet.with_chain_id(1u64); // Mainnet
-        let wallet = Arc::new(SignerMiddleware::new(provider.clone(), wallet));
+        let wallet = evm_context(network).await?.signer()?;
 
         let token_address = token_address
             .parse::<Address>()
@@ -581,16 +1007,268 @@ pub mod ethereum_chain {
         let amount =
             U256::from_dec_str(amount).map_err(|e| format!("Failed to parse amount: {}", e))?;
 
-        let tx = erc20.approve(spender, amount);
-        let pending_tx = tx
-            .send()
+        for tx in approve_transactions(&erc20, spender, amount, ApprovalPolicy::from_env()).await? {
+            send_and_confirm(&wallet, tx, &TX_MANAGER_CONFIG)
+                .await
+                .map_err(|e| format!("Transaction failed: {}", e))?;
+        }
+
+        Ok(())
+    }
+
+    /// Permit2, same address on every chain.
+    pub const PERMIT2_ADDRESS: &str = "0x000000000022D473030F116dDEE9F6B43aC78BA3";
+
+    /// Approves `U256::MAX` of the token to Permit2 when its allowance doesn't
+    /// cover `amount`, whatever `APPROVAL_POLICY` says: Permit2 signature
+    /// transfers are signed per fill, the approve to Permit2 is sent once.
+    pub async fn approve_permit2(
+        network: &EvmNetwork,
+        token_address: Address,
+        amount: U256,
+    ) -> Result<(), String> {
+        let wallet = evm_context(network).await?.signer()?;
+        let erc20 = ERC20::new(token_address, wallet.clone());
+        let permit2 = Address::from_str(PERMIT2_ADDRESS)
+            .map_err(|e| format!("Invalid Permit2 address: {}", e))?;
+
+        for tx in approve_transactions(&erc20, permit2, amount, ApprovalPolicy::Max).await? {
+            send_and_confirm(&wallet, tx, &TX_MANAGER_CONFIG)
+                .await
+                .map_err(|e| format!("Transaction failed: {}", e))?;
+        }
+
+        Ok(())
+    }
+
+    /// Seconds a signed permit stays valid.
+    const PERMIT_DEADLINE_SECS: u64 = 1800;
+
+    abigen!(
+        PermitDomain,
+        r#"[
+            function DOMAIN_SEPARATOR() external view returns (bytes32)
+            function nonces(address owner) external view returns (uint256)
+        ]"#
+    );
+
+    /// How the executor gets the solver's bridge token, `PERMIT_STRATEGY`:
+    /// `approve` (default) pulls it through an allowance, `erc2612` signs an
+    /// EIP-2612 permit per fill and `permit2` a Permit2 signature transfer per
+    /// fill, so no approve is sent to the executor.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum PermitStrategy {
+        Approve,
+        Erc2612,
+        Permit2,
+    }
+
+    impl PermitStrategy {
+        pub fn from_env() -> Self {
+            match env::var("PERMIT_STRATEGY").as_deref() {
+                Ok("erc2612") => PermitStrategy::Erc2612,
+                Ok("permit2") => PermitStrategy::Permit2,
+                _ => PermitStrategy::Approve,
+            }
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct SignedPermit {
+        pub nonce: U256,
+        pub deadline: U256,
+        pub signature: Bytes, // r, s, v
+    }
+
+    fn permit_deadline() -> Result<U256, String> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|e| format!("Invalid system time: {}", e))?;
+        Ok(U256::from(now.as_secs() + PERMIT_DEADLINE_SECS))
+    }
+
+    /// Signs the EIP-712 digest of `struct_hash` under the domain read from
+    /// `verifying_contract`, so name, version and chain id always match it.
+    async fn sign_typed_hash(
+        client: &Arc<EvmClient>,
+        verifying_contract: Address,
+        struct_hash: [u8; 32],
+    ) -> Result<Bytes, String> {
+        let domain_separator = PermitDomain::new(verifying_contract, client.clone())
+            .domain_separator()
+            .call()
             .await
-            .map_err(|e| format!("Failed to send transaction: {}", e))?;
+            .map_err(|e| format!("Failed to get DOMAIN_SEPARATOR: {}", e))?;
+
+        let mut message = Vec::with_capacity(66);
+        message.extend_from_slice(b"\x19\x01");
+        message.extend_from_slice(&domain_separator);
+        message.extend_from_slice(&struct_hash);
+
+        let signature = client
+            .signer()
+            .sign_hash(H256::from(keccak256(message)))
+            .map_err(|e| format!("Failed to sign permit: {}", e))?;
+        Ok(Bytes::from(signature.to_vec()))
+    }
 
-        pending_tx
+    /// EIP-2612 `permit` letting `spender` pull `value` of `token` from the
+    /// solver wallet.
+    pub async fn sign_erc2612_permit(
+        client: &Arc<EvmClient>,
+        token: Address,
+        spender: Address,
+        value: U256,
+    ) -> Result<SignedPermit, String> {
+        let owner = client.address();
+        let nonce = PermitDomain::new(token, client.clone())
+            .nonces(owner)
+            .call()
             .await
-            .map_err(|e| format!("Transaction failed: {}", e))?;
+            .map_err(|e| format!("Token has no EIP-2612 nonces: {}", e))?;
+        let deadline = permit_deadline()?;
 
-        Ok(())
+        let struct_hash = keccak256(encode(&[
+            Token::FixedBytes(
+                keccak256(
+                    "Permit(address owner,address spender,uint256 value,uint256 nonce,uint256 deadline)",
+                )
+                .to_vec(),
+            ),
+            Token::Address(owner),
+            Token::Address(spender),
+            Token::Uint(value),
+            Token::Uint(nonce),
+            Token::Uint(deadline),
+        ]));
+
+        Ok(SignedPermit {
+            nonce,
+            deadline,
+            signature: sign_typed_hash(client, token, struct_hash).await?,
+        })
+    }
+
+    /// Permit2 `permitTransferFrom` letting `spender` pull `amount` of `token`
+    /// once. Nonces are unordered, any unused one works. The token needs a
+    /// one-time approve to Permit2.
+    pub async fn sign_permit2_transfer(
+        client: &Arc<EvmClient>,
+        token: Address,
+        spender: Address,
+        amount: U256,
+    ) -> Result<SignedPermit, String> {
+        let permit2 = Address::from_str(PERMIT2_ADDRESS).expect("valid Permit2 address");
+        let nonce = U256::from(
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_err(|e| format!("Invalid system time: {}", e))?
+                .as_nanos(),
+        );
+        let deadline = permit_deadline()?;
+
+        let token_permissions = keccak256(encode(&[
+            Token::FixedBytes(
+                keccak256("TokenPermissions(address token,uint256 amount)").to_vec(),
+            ),
+            Token::Address(token),
+            Token::Uint(amount),
+        ]));
+        let struct_hash = keccak256(encode(&[
+            Token::FixedBytes(
+                keccak256(
+                    "PermitTransferFrom(TokenPermissions permitted,address spender,uint256 nonce,uint256 deadline)TokenPermissions(address token,uint256 amount)",
+                )
+                .to_vec(),
+            ),
+            Token::FixedBytes(token_permissions.to_vec()),
+            Token::Address(spender),
+            Token::Uint(nonce),
+            Token::Uint(deadline),
+        ]));
+
+        Ok(SignedPermit {
+            nonce,
+            deadline,
+            signature: sign_typed_hash(client, permit2, struct_hash).await?,
+        })
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::chains::{SwapTransferInput, SwapTransferOutput};
+        use ethers::abi::Tokenizable;
+
+        /// An escrow whose `getIntentInfo` returns `info` once.
+        fn escrow_returning(info: IntentInfo) -> Escrow<Provider<MockProvider>> {
+            let (provider, mock) = Provider::mocked();
+            let returned = Bytes::from(encode(&[info.into_token()]));
+            mock.push::<Bytes, _>(returned).unwrap();
+            Escrow::new(Address::repeat_byte(0xe5), Arc::new(provider))
+        }
+
+        fn won_intent(winner_solver: &str, amount_out: &str) -> IntentInfo {
+            IntentInfo {
+                token_in: Address::repeat_byte(0xbb),
+                amount_in: U256::from(5),
+                src_user: Address::repeat_byte(0xaa),
+                token_out: format!("{:?}", Address::repeat_byte(0xcc)),
+                amount_out: amount_out.to_string(),
+                dst_user: format!("{:?}", Address::repeat_byte(0xaa)),
+                winner_solver: winner_solver.to_string(),
+                timeout: U256::zero(),
+                single_domain: false,
+            }
+        }
+
+        fn cross_chain_intent() -> PostIntentInfo {
+            PostIntentInfo {
+                function_name: "transfer".to_string(),
+                src_chain: "arbitrum".to_string(),
+                dst_chain: "ethereum".to_string(),
+                inputs: OperationInput::SwapTransfer(SwapTransferInput {
+                    token_in: format!("{:?}", Address::repeat_byte(0xbb)),
+                    amount_in: "5".to_string(),
+                    src_chain_user: format!("{:?}", Address::repeat_byte(0xaa)),
+                    timeout: "0".to_string(),
+                }),
+                outputs: OperationOutput::SwapTransfer(SwapTransferOutput {
+                    token_out: format!("{:?}", Address::repeat_byte(0xcc)),
+                    amount_out: "1000".to_string(),
+                    dst_chain_user: format!("{:?}", Address::repeat_byte(0xaa)),
+                }),
+            }
+        }
+
+        async fn check(info: IntentInfo, amount: &str) -> Result<(), String> {
+            let network = evm_network("ethereum").unwrap();
+            let escrow = escrow_returning(info);
+            check_escrow_intent(&escrow, network, &cross_chain_intent(), "1", amount).await
+        }
+
+        #[tokio::test]
+        async fn accepts_an_intent_won_for_the_escrow_amount() {
+            // the auctioneer may record the winner in any casing
+            let winner = SOLVER_ADDRESSES[0].to_lowercase();
+            assert_eq!(check(won_intent(&winner, "1000"), "1000").await, Ok(()));
+        }
+
+        #[tokio::test]
+        async fn rejects_an_intent_won_by_another_solver() {
+            let winner = format!("{:?}", Address::repeat_byte(0xee));
+            let err = check(won_intent(&winner, "1000"), "1000")
+                .await
+                .unwrap_err();
+            assert!(err.contains("not this solver"), "{err}");
+        }
+
+        #[tokio::test]
+        async fn rejects_a_bid_below_the_escrow_amount() {
+            let err = check(won_intent(SOLVER_ADDRESSES[0], "1000"), "999")
+                .await
+                .unwrap_err();
+            assert!(err.contains("requires 1000 token_out"), "{err}");
+        }
     }
 }
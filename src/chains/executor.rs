@@ -0,0 +1,393 @@
+pub mod solver_executor {
+    use crate::chains::ethereum::ethereum_chain::{
+        approve_permit2, build_evm_swap, evm_context, sign_erc2612_permit, sign_permit2_transfer,
+        EvmNetwork, PermitStrategy,
+    };
+    use crate::chains::get_solver_address;
+    use crate::chains::tx_manager::tx_manager::{send_and_confirm, TX_MANAGER_CONFIG};
+    use crate::chains::{OperationInput, OperationOutput};
+    use crate::routers::swap_router::{SwapParams, SwapSide};
+    use crate::routers::QuoteResult;
+    use crate::PostIntentInfo;
+    use ethers::abi::{encode, Token};
+    use ethers::prelude::*;
+    use ethers::utils::get_create2_address;
+    use num_bigint::BigInt;
+    use std::str::FromStr;
+
+    // Source in contracts/SolverExecutor.sol
+    abigen!(
+        SolverExecutor,
+        r#"[
+            struct Swap { address target; address spender; address tokenIn; uint256 amountIn; uint256 value; bytes data; }
+            struct Fill { address escrow; string intentId; string solverOut; address bridgeToken; uint256 bridgeAmount; address tokenOut; uint256 amountOut; Swap swapOut; Swap swapBack; address tokenIn; }
+            struct PullPermit { uint8 kind; uint256 nonce; uint256 deadline; bytes signature; }
+            function owner() external view returns (address)
+            function fill(Fill f) external payable
+            function fillWithPermit(Fill f, PullPermit p) external payable
+            function sweep(address token) external
+            error NotOwner()
+            error InvalidPermit()
+            error TokenCallFailed(address token, bytes4 selector)
+            error SwapFailed(address target, bytes reason)
+            error EscrowFailed(bytes reason)
+        ]"#
+    );
+
+    // PullPermit.kind
+    const PERMIT_ERC2612: u8 = 1;
+    const PERMIT_PERMIT2: u8 = 2;
+
+    /// CREATE2 deployment proxy at the same address on Ethereum and its L2s,
+    /// https://github.com/Arachnid/deterministic-deployment-proxy
+    const CREATE2_DEPLOYER: &str = "0x4e59b44847b379578588920cA78FbF26c0B4956C";
+
+    fn to_u256(amount: &BigInt) -> Result<U256, String> {
+        U256::from_dec_str(&amount.to_string()).map_err(|e| format!("Invalid amount: {}", e))
+    }
+
+    fn no_swap() -> Swap {
+        Swap {
+            target: Address::zero(),
+            spender: Address::zero(),
+            token_in: Address::zero(),
+            amount_in: U256::zero(),
+            value: U256::zero(),
+            data: Bytes::default(),
+        }
+    }
+
+    /// Builds a swap through `router` with the executor as taker and receiver.
+    /// `max_amount_in` is what the executor approves the router for.
+    async fn executor_swap(
+        network: &EvmNetwork,
+        params: &SwapParams,
+        router: Option<&str>,
+        max_amount_in: Option<&BigInt>,
+    ) -> Result<Swap, String> {
+        let swap = build_evm_swap(network, params, router, max_amount_in).await?;
+
+        Ok(Swap {
+            target: swap.to,
+            spender: swap.allowance_target,
+            token_in: Address::from_str(&params.token_in)
+                .map_err(|e| format!("Invalid token_in address: {}", e))?,
+            amount_in: to_u256(max_amount_in.unwrap_or(&swap.amount_in))?,
+            value: swap.value,
+            data: swap.data,
+        })
+    }
+
+    /// The fill of `intent` without its swaps, paying `amount` token_out
+    /// straight from the bridge token pulled. On single domain intents the
+    /// escrow releases token_in to the executor, which swaps it back.
+    fn base_fill(
+        executor: Address,
+        escrow: &str,
+        bridge_token: &str,
+        intent: &PostIntentInfo,
+        intent_id: &str,
+        amount: &BigInt,
+    ) -> Result<Fill, String> {
+        let mut token_in = String::default();
+        let mut token_out = String::default();
+
+        if let OperationOutput::SwapTransfer(transfer_output) = &intent.outputs {
+            token_out = transfer_output.token_out.clone();
+        }
+        if let OperationInput::SwapTransfer(transfer_input) = &intent.inputs {
+            token_in = transfer_input.token_in.clone();
+        }
+
+        let single_domain = intent.src_chain == intent.dst_chain;
+        let solver_out = if single_domain {
+            format!("{:?}", executor)
+        } else {
+            get_solver_address(&intent.src_chain)
+                .ok_or_else(|| format!("No solver address for {}", intent.src_chain))?
+                .to_string()
+        };
+
+        let parse = |address: &str, what: &str| {
+            Address::from_str(address).map_err(|e| format!("Invalid {} address: {}", what, e))
+        };
+        Ok(Fill {
+            escrow: parse(escrow, "escrow")?,
+            intent_id: intent_id.to_string(),
+            solver_out,
+            bridge_token: parse(bridge_token, "bridge token")?,
+            bridge_amount: to_u256(amount)?,
+            token_out: parse(&token_out, "token_out")?,
+            amount_out: to_u256(amount)?,
+            swap_out: no_swap(),
+            swap_back: no_swap(),
+            // token_in is only received here on single domain intents
+            token_in: if single_domain {
+                parse(&token_in, "token_in")?
+            } else {
+                Address::zero()
+            },
+        })
+    }
+
+    /// Fills a "swap" intent through the executor at `executor`: bridge token
+    /// -> token_out, approve to escrow, sendFundsToUser and, on single domain,
+    /// token_in -> bridge token, all in one transaction. The bridge token is
+    /// pulled from the solver wallet, which must approve it to the executor
+    /// once, unless `PERMIT_STRATEGY` signs a permit for every fill. The escrow
+    /// only lets the winner pay, so the executor is the EVM address registered
+    /// with the auctioneer (`SOLVER_EXECUTOR`) and must be deployed on `network`.
+    pub async fn executor_fill(
+        network: &EvmNetwork,
+        executor: Address,
+        escrow: &str,
+        intent: &PostIntentInfo,
+        intent_id: &str,
+        amount: &str,
+        quote: &QuoteResult,
+    ) -> Result<TxHash, String> {
+        let mut token_in = String::default();
+        let mut token_out = String::default();
+        let mut amount_in = String::default();
+
+        if let OperationOutput::SwapTransfer(transfer_output) = &intent.outputs {
+            token_out = transfer_output.token_out.clone();
+        }
+        if let OperationInput::SwapTransfer(transfer_input) = &intent.inputs {
+            token_in = transfer_input.token_in.clone();
+            amount_in = transfer_input.amount_in.clone();
+        }
+
+        let code = evm_context(network)
+            .await?
+            .provider
+            .get_code(executor, None)
+            .await
+            .map_err(|e| format!("Failed to get executor code: {}", e))?;
+        if code.is_empty() {
+            return Err(format!(
+                "No SolverExecutor at {:?} on {}, run deploy-executor {}",
+                executor, network.name, network.name
+            ));
+        }
+
+        let bridge_token = network.bridge_token;
+        let amount = BigInt::from_str(amount).map_err(|e| format!("Invalid amount: {}", e))?;
+        let single_domain = intent.src_chain == intent.dst_chain;
+        let mut fill = base_fill(executor, escrow, bridge_token, intent, intent_id, &amount)?;
+
+        // swap USDT -> token_out
+        if !token_out.eq_ignore_ascii_case(bridge_token) {
+            let swap_params = SwapParams {
+                token_in: bridge_token.to_string(),
+                token_out: token_out.clone(),
+                amount: amount.clone(),
+                side: SwapSide::ExactOut,
+                sender: format!("{:?}", executor),
+                receiver: format!("{:?}", executor),
+                slippage_bps: 100,
+            };
+            // never spend more USDT than the bid budgeted for the swap
+            fill.swap_out = executor_swap(
+                network,
+                &swap_params,
+                quote.dst_router,
+                Some(&quote.bridge_amount),
+            )
+            .await?;
+            fill.bridge_amount = to_u256(&quote.bridge_amount)?;
+        }
+
+        // swap token_in -> USDT
+        if single_domain && !token_in.eq_ignore_ascii_case(bridge_token) {
+            let swap_params = SwapParams {
+                token_in: token_in.clone(),
+                token_out: bridge_token.to_string(),
+                amount: BigInt::from_str(&amount_in)
+                    .map_err(|e| format!("Invalid amount_in: {}", e))?,
+                side: SwapSide::ExactIn,
+                sender: format!("{:?}", executor),
+                receiver: format!("{:?}", executor),
+                slippage_bps: 100,
+            };
+            fill.swap_back = executor_swap(network, &swap_params, quote.src_router, None).await?;
+        }
+
+        let client = evm_context(network).await?.signer()?;
+        let contract = SolverExecutor::new(executor, client.clone());
+        let call = match PermitStrategy::from_env() {
+            _ if fill.bridge_amount.is_zero() => contract.fill(fill),
+            PermitStrategy::Approve => contract.fill(fill),
+            PermitStrategy::Erc2612 => {
+                let permit =
+                    sign_erc2612_permit(&client, fill.bridge_token, executor, fill.bridge_amount)
+                        .await?;
+                let pull = PullPermit {
+                    kind: PERMIT_ERC2612,
+                    nonce: permit.nonce,
+                    deadline: permit.deadline,
+                    signature: permit.signature,
+                };
+                contract.fill_with_permit(fill, pull)
+            }
+            PermitStrategy::Permit2 => {
+                // Permit2 itself pulls through a regular allowance, approved once
+                approve_permit2(network, fill.bridge_token, fill.bridge_amount).await?;
+                let permit =
+                    sign_permit2_transfer(&client, fill.bridge_token, executor, fill.bridge_amount)
+                        .await?;
+                let pull = PullPermit {
+                    kind: PERMIT_PERMIT2,
+                    nonce: permit.nonce,
+                    deadline: permit.deadline,
+                    signature: permit.signature,
+                };
+                contract.fill_with_permit(fill, pull)
+            }
+        };
+        let receipt = send_and_confirm(&client, call.tx, &TX_MANAGER_CONFIG)
+            .await
+            .map_err(|e| format!("Executor fill failed: {}", e))?;
+
+        Ok(receipt.transaction_hash)
+    }
+
+    /// Address of the executor owned by `owner` deployed through
+    /// `CREATE2_DEPLOYER`, and the deployment calldata: salt ++ creation code.
+    fn create2_deployment(owner: Address, bytecode: &str) -> Result<(Address, Bytes), String> {
+        let mut init_code = hex::decode(bytecode.trim().trim_start_matches("0x"))
+            .map_err(|e| format!("Invalid bytecode: {}", e))?;
+        init_code.extend(encode(&[Token::Address(owner)]));
+
+        let deployer = Address::from_str(CREATE2_DEPLOYER).unwrap();
+        let salt = [0u8; 32];
+        let address = get_create2_address(deployer, salt, &init_code);
+
+        Ok((address, [salt.to_vec(), init_code].concat().into()))
+    }
+
+    /// Deploys `SolverExecutor` on `network`, owned by the `ETHEREUM_PKEY`
+    /// wallet. `bytecode` is the hex creation code from `solc --bin`. It goes
+    /// through CREATE2, so the same bytecode lands at the same address on every
+    /// network, the one address registered with the auctioneer.
+    pub async fn deploy_solver_executor(
+        network: &EvmNetwork,
+        bytecode: &str,
+    ) -> Result<Address, String> {
+        let client = evm_context(network).await?.signer()?;
+        let (executor, calldata) = create2_deployment(client.address(), bytecode)?;
+
+        let code = client
+            .get_code(executor, None)
+            .await
+            .map_err(|e| format!("Failed to get code: {}", e))?;
+        if !code.is_empty() {
+            println!(
+                "SolverExecutor already deployed on {} at {:?}",
+                network.name, executor
+            );
+            return Ok(executor);
+        }
+
+        let deployer = Address::from_str(CREATE2_DEPLOYER).unwrap();
+        let tx = TransactionRequest::new().to(deployer).data(calldata);
+        send_and_confirm(&client, tx.into(), &TX_MANAGER_CONFIG)
+            .await
+            .map_err(|e| format!("Deployment failed: {}", e))?;
+
+        let code = client
+            .get_code(executor, None)
+            .await
+            .map_err(|e| format!("Failed to get code: {}", e))?;
+        if code.is_empty() {
+            return Err(format!("No code at {:?} after the deployment", executor));
+        }
+        Ok(executor)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::chains::{SwapTransferInput, SwapTransferOutput, SOLVER_ADDRESSES};
+
+        const ESCROW: &str = "0x3d34b4Ff589f9B97f8a5540feC1c2ABAB9D4C64c";
+        const USDT: &str = "0xdAC17F958D2ee523a2206206994597C13D831ec7";
+        const WETH: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+
+        fn intent(src_chain: &str, dst_chain: &str) -> PostIntentInfo {
+            PostIntentInfo {
+                function_name: "swap".to_string(),
+                src_chain: src_chain.to_string(),
+                dst_chain: dst_chain.to_string(),
+                inputs: OperationInput::SwapTransfer(SwapTransferInput {
+                    token_in: WETH.to_string(),
+                    amount_in: "1000000000000000000".to_string(),
+                    src_chain_user: "0x0000000000000000000000000000000000000001".to_string(),
+                    timeout: "1000".to_string(),
+                }),
+                outputs: OperationOutput::SwapTransfer(SwapTransferOutput {
+                    token_out: USDT.to_string(),
+                    amount_out: "2000000000".to_string(),
+                    dst_chain_user: "0x0000000000000000000000000000000000000002".to_string(),
+                }),
+            }
+        }
+
+        #[test]
+        fn single_domain_fill_releases_token_in_to_the_executor() {
+            let executor = Address::repeat_byte(0xee);
+            let amount = BigInt::from(2_000_000_000u64);
+            let fill = base_fill(
+                executor,
+                ESCROW,
+                USDT,
+                &intent("ethereum", "ethereum"),
+                "7",
+                &amount,
+            )
+            .unwrap();
+
+            assert_eq!(fill.solver_out, format!("{:?}", executor));
+            assert_eq!(fill.token_in, Address::from_str(WETH).unwrap());
+            assert_eq!(fill.intent_id, "7");
+            assert_eq!(fill.amount_out, U256::from(2_000_000_000u64));
+            assert_eq!(fill.bridge_amount, fill.amount_out);
+            assert_eq!(fill.swap_out.target, Address::zero());
+        }
+
+        #[test]
+        fn cross_chain_fill_releases_token_in_to_the_source_chain_solver() {
+            let amount = BigInt::from(2_000_000_000u64);
+            let fill = base_fill(
+                Address::repeat_byte(0xee),
+                ESCROW,
+                USDT,
+                &intent("solana", "ethereum"),
+                "7",
+                &amount,
+            )
+            .unwrap();
+
+            assert_eq!(fill.solver_out, SOLVER_ADDRESSES[1]);
+            assert_eq!(fill.token_in, Address::zero());
+        }
+
+        #[test]
+        fn create2_address_only_depends_on_owner_and_bytecode() {
+            let owner = Address::repeat_byte(1);
+            let (address, calldata) = create2_deployment(owner, "0x6080").unwrap();
+
+            assert_eq!(create2_deployment(owner, "6080").unwrap().0, address);
+            assert_ne!(
+                create2_deployment(Address::repeat_byte(2), "0x6080")
+                    .unwrap()
+                    .0,
+                address
+            );
+            // zero salt, creation code, owner constructor argument
+            assert_eq!(&calldata[..32], &[0u8; 32]);
+            assert_eq!(&calldata[32..34], &[0x60, 0x80]);
+            assert_eq!(&calldata[46..], owner.as_bytes());
+        }
+    }
+}
@@ -1,10 +1,17 @@
+pub mod bundle_relay;
+pub mod escrow_listener;
 pub mod ethereum;
+pub mod executor;
+pub mod nonce_manager;
+pub mod revert;
 pub mod solana;
+pub mod tx_manager;
 
 use lazy_static::lazy_static;
 use std::collections::HashMap;
 
 use std::env;
+use ethereum::ethereum_chain::{evm_network, EVM_NETWORKS};
 use ethers::prelude::*;
 use ethers::signers::LocalWallet;
 use ethers::utils::hash_message;
@@ -83,11 +90,23 @@ pub struct PostIntentInfo {
     pub outputs: OperationOutput,
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, EnumString, Serialize, Deserialize)]
-#[strum(serialize_all = "lowercase")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum Blockchain {
-    Ethereum,
     Solana,
+    Evm(u64), // chain id of one of the EVM_NETWORKS
+}
+
+impl FromStr for Blockchain {
+    type Err = String;
+
+    fn from_str(chain: &str) -> Result<Self, Self::Err> {
+        if chain == "solana" {
+            return Ok(Blockchain::Solana);
+        }
+        evm_network(chain)
+            .map(|network| Blockchain::Evm(network.chain_id))
+            .ok_or_else(|| format!("chain not supported: {chain}"))
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, EnumString, Serialize, Deserialize)]
@@ -103,7 +122,7 @@ struct TokenInfo {
 }
 
 pub static SOLVER_ADDRESSES: &[&str] = &[
-    "0x460c026eEEA4953D66c31EF74BCEAF2411e21C9A", // ethereum
+    "0x460c026eEEA4953D66c31EF74BCEAF2411e21C9A", // ethereum and every other EVM network
     "3E5Ntj6GP3a9FqnRFnfzFKbq9JjPDns6PsFPASPtHm9s", // solana
 ];
 
@@ -112,10 +131,9 @@ lazy_static! {
         let mut m = HashMap::new();
 
         let mut usdt_addresses = HashMap::new();
-        usdt_addresses.insert(
-            Blockchain::Ethereum,
-            "0xdAC17F958D2ee523a2206206994597C13D831ec7",
-        );
+        for network in EVM_NETWORKS {
+            usdt_addresses.insert(Blockchain::Evm(network.chain_id), network.bridge_token);
+        }
         usdt_addresses.insert(
             Blockchain::Solana,
             "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB",
@@ -133,6 +151,12 @@ lazy_static! {
     pub static ref SOLVER_ID: String = env::var("SOLVER_ID").unwrap_or_else(|_| String::from(""));
     pub static ref SOLVER_PRIVATE_KEY: String =
         env::var("ETHEREUM_PKEY").unwrap_or_else(|_| String::from(""));
+    // SolverExecutor deployed at the same address on every EVM network (see
+    // `deploy-executor`), it then wins and pays EVM intents instead of the wallet
+    pub static ref SOLVER_EXECUTOR: Option<Address> = env::var("SOLVER_EXECUTOR")
+        .ok()
+        .filter(|executor| !executor.is_empty())
+        .map(|executor| Address::from_str(&executor).expect("SOLVER_EXECUTOR must be an address"));
 }
 
 pub fn get_token_info(token: &str, blockchain: &str) -> Option<(&'static str, u32)> {
@@ -143,6 +167,32 @@ pub fn get_token_info(token: &str, blockchain: &str) -> Option<(&'static str, u3
     Some((address, info.decimals))
 }
 
+pub fn get_solver_address(blockchain: &str) -> Option<&'static str> {
+    // the same EVM address solves on every EVM network
+    match Blockchain::from_str(blockchain).ok()? {
+        Blockchain::Evm(_) => SOLVER_ADDRESSES.first().copied(),
+        Blockchain::Solana => SOLVER_ADDRESSES.get(1).copied(),
+    }
+}
+
+/// Addresses registered with the auctioneer, i.e. the escrow's winner per
+/// chain: the executor on EVM networks when `SOLVER_EXECUTOR` is set, as the
+/// escrow only lets the winner call `sendFundsToUser`.
+pub fn registered_solver_addresses() -> Vec<String> {
+    SOLVER_ADDRESSES
+        .iter()
+        .enumerate()
+        .map(|(i, address)| match (i, *SOLVER_EXECUTOR) {
+            (0, Some(executor)) => ethers::utils::to_checksum(&executor, None),
+            _ => address.to_string(),
+        })
+        .collect()
+}
+
+pub fn is_evm_chain(blockchain: &str) -> bool {
+    matches!(Blockchain::from_str(blockchain), Ok(Blockchain::Evm(_)))
+}
+
 pub async fn create_keccak256_signature(
     json_data: &mut Value,
     private_key: String,
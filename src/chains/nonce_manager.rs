@@ -0,0 +1,301 @@
+pub mod nonce_manager {
+    use ethers::prelude::*;
+    use lazy_static::lazy_static;
+    use std::collections::{BTreeSet, HashMap};
+    use std::sync::Arc;
+    use tokio::sync::{Mutex, OwnedMutexGuard};
+
+    lazy_static! {
+        /// Shared by every EVM send, so concurrent intents never reuse a nonce.
+        pub static ref NONCE_MANAGER: NonceManager = NonceManager::default();
+    }
+
+    #[derive(Debug, Default)]
+    struct AccountNonces {
+        next: Option<U256>,
+        reserved: BTreeSet<U256>, // handed out, not broadcast yet
+        // the local counter may be off, check the node's pending count
+        stale: bool,
+    }
+
+    impl AccountNonces {
+        fn needs_sync(&self) -> bool {
+            self.next.is_none() || self.stale
+        }
+
+        /// Moves the local counter to the node's pending transaction count:
+        /// forward when something else sent from the account, back when nonces
+        /// handed out never reached the mempool (a gap the chain can't fill).
+        fn sync(&mut self, pending: U256, chain_id: u64, account: Address) {
+            match self.next {
+                None => self.next = Some(pending),
+                Some(next) if pending > next => {
+                    println!(
+                        "Nonce of {:?} on chain {chain_id} behind the node ({next} < {pending}), resyncing",
+                        account
+                    );
+                    self.next = Some(pending);
+                }
+                Some(next) if pending < next && self.reserved.is_empty() => {
+                    println!(
+                        "Nonces {pending}..{next} of {:?} on chain {chain_id} never reached the mempool, resyncing",
+                        account
+                    );
+                    self.next = Some(pending);
+                }
+                // reservations in flight may still fill the gap, check again
+                Some(next) if pending < next => return,
+                Some(_) => {}
+            }
+            self.stale = false;
+        }
+
+        fn take(&mut self) -> U256 {
+            let next = self.next.unwrap_or_default();
+            self.next = Some(next + 1);
+            self.reserved.insert(next);
+            next
+        }
+    }
+
+    type SharedNonces = Arc<Mutex<AccountNonces>>;
+
+    /// Hands out nonces per (chain id, account), each account behind its own
+    /// lock. The node's pending transaction count is only fetched the first
+    /// time an account is used and after the local counter went stale, i.e. a
+    /// nonce other than the latest was released or a send hit a nonce error.
+    #[derive(Debug, Default)]
+    pub struct NonceManager {
+        accounts: Mutex<HashMap<(u64, Address), SharedNonces>>,
+    }
+
+    impl NonceManager {
+        async fn account(&self, chain_id: u64, account: Address) -> SharedNonces {
+            self.accounts
+                .lock()
+                .await
+                .entry((chain_id, account))
+                .or_default()
+                .clone()
+        }
+
+        /// Takes the account's lock, synced with the node when needed. Every
+        /// other `reserve` for the account waits until it is dropped.
+        pub async fn lock<M: Middleware>(
+            &self,
+            client: &M,
+            chain_id: u64,
+            account: Address,
+        ) -> Result<AccountLock, String> {
+            let mut nonces = self.account(chain_id, account).await.lock_owned().await;
+
+            if nonces.needs_sync() {
+                let pending = client
+                    .get_transaction_count(account, Some(BlockNumber::Pending.into()))
+                    .await
+                    .map_err(|e| format!("Failed to fetch pending nonce: {}", e))?;
+                nonces.sync(pending, chain_id, account);
+            }
+
+            Ok(AccountLock { nonces })
+        }
+
+        pub async fn reserve<M: Middleware>(
+            &self,
+            client: &M,
+            chain_id: u64,
+            account: Address,
+        ) -> Result<U256, String> {
+            Ok(self.lock(client, chain_id, account).await?.reserve())
+        }
+
+        /// The transaction with `nonce` was accepted by the node.
+        pub async fn sent(&self, chain_id: u64, account: Address, nonce: U256) {
+            let nonces = self.account(chain_id, account).await;
+            nonces.lock().await.reserved.remove(&nonce);
+        }
+
+        /// The transaction with `nonce` was never broadcast, the nonce is reused
+        /// when it is the latest one, otherwise the next `reserve` resyncs.
+        pub async fn release(&self, chain_id: u64, account: Address, nonce: U256) {
+            let nonces = self.account(chain_id, account).await;
+            let mut nonces = nonces.lock().await;
+            nonces.reserved.remove(&nonce);
+            if nonces.next == Some(nonce + 1) {
+                nonces.next = Some(nonce);
+            } else {
+                nonces.stale = true;
+            }
+        }
+
+        /// The node rejected a nonce or another transaction used it, the next
+        /// `reserve` checks the node's pending count.
+        pub async fn resync(&self, chain_id: u64, account: Address) {
+            let nonces = self.account(chain_id, account).await;
+            nonces.lock().await.stale = true;
+        }
+    }
+
+    /// An account's nonces held for a whole batch, e.g. a private bundle:
+    /// a public transaction taking the next nonce meanwhile would sit behind
+    /// the bundle's, missing from the mempool, until the bundle lands.
+    #[derive(Debug)]
+    pub struct AccountLock {
+        nonces: OwnedMutexGuard<AccountNonces>,
+    }
+
+    impl AccountLock {
+        pub fn reserve(&mut self) -> U256 {
+            self.nonces.take()
+        }
+
+        /// The transaction with `nonce` was accepted by the node.
+        pub fn sent(&mut self, nonce: U256) {
+            self.nonces.reserved.remove(&nonce);
+        }
+
+        /// Whether the nonces got used is up to the node, the next `reserve`
+        /// checks its pending count.
+        pub fn resync(&mut self) {
+            self.nonces.stale = true;
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn mocked(pending: &[u64]) -> Provider<MockProvider> {
+            let (provider, mock) = Provider::mocked();
+            // responses are served last pushed first
+            for pending in pending.iter().rev() {
+                mock.push(U256::from(*pending)).unwrap();
+            }
+            provider
+        }
+
+        #[tokio::test]
+        async fn reserves_from_the_pending_count_once() {
+            let manager = NonceManager::default();
+            let client = mocked(&[7]);
+            let account = Address::repeat_byte(1);
+
+            assert_eq!(
+                manager.reserve(&client, 1, account).await,
+                Ok(U256::from(7))
+            );
+            manager.sent(1, account, U256::from(7)).await;
+            // no pending count left in the mock, served from the local counter
+            assert_eq!(
+                manager.reserve(&client, 1, account).await,
+                Ok(U256::from(8))
+            );
+        }
+
+        #[tokio::test]
+        async fn reuses_the_latest_released_nonce() {
+            let manager = NonceManager::default();
+            let client = mocked(&[3]);
+            let account = Address::repeat_byte(1);
+
+            let nonce = manager.reserve(&client, 1, account).await.unwrap();
+            manager.release(1, account, nonce).await;
+            assert_eq!(manager.reserve(&client, 1, account).await, Ok(nonce));
+        }
+
+        #[tokio::test]
+        async fn resyncs_a_gap_left_by_a_released_nonce() {
+            let manager = NonceManager::default();
+            let client = mocked(&[3, 3]);
+            let account = Address::repeat_byte(1);
+
+            let first = manager.reserve(&client, 1, account).await.unwrap();
+            let second = manager.reserve(&client, 1, account).await.unwrap();
+            // 3 never reaches the mempool, so 4 is stuck behind it
+            manager.release(1, account, first).await;
+            manager.release(1, account, second).await;
+
+            assert_eq!(
+                manager.reserve(&client, 1, account).await,
+                Ok(U256::from(3))
+            );
+        }
+
+        #[tokio::test]
+        async fn jumps_forward_when_the_account_sent_elsewhere() {
+            let manager = NonceManager::default();
+            let client = mocked(&[3, 10]);
+            let account = Address::repeat_byte(1);
+
+            let nonce = manager.reserve(&client, 1, account).await.unwrap();
+            manager.sent(1, account, nonce).await;
+            manager.resync(1, account).await;
+
+            assert_eq!(
+                manager.reserve(&client, 1, account).await,
+                Ok(U256::from(10))
+            );
+        }
+
+        #[tokio::test]
+        async fn reserve_waits_for_a_pending_bundle() {
+            let manager = Arc::new(NonceManager::default());
+            let account = Address::repeat_byte(1);
+
+            let mut bundle = manager.lock(&mocked(&[3]), 1, account).await.unwrap();
+            let nonces = [bundle.reserve(), bundle.reserve()];
+
+            // a public send while the bundle waits for its block
+            let public = {
+                let manager = manager.clone();
+                tokio::spawn(async move { manager.reserve(&mocked(&[]), 1, account).await })
+            };
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            assert!(!public.is_finished());
+
+            // the bundle landed
+            for nonce in nonces {
+                bundle.sent(nonce);
+            }
+            drop(bundle);
+            assert_eq!(public.await.unwrap(), Ok(U256::from(5)));
+        }
+
+        #[tokio::test]
+        async fn reserve_after_a_failed_bundle_resyncs() {
+            let manager = Arc::new(NonceManager::default());
+            let account = Address::repeat_byte(1);
+
+            let mut bundle = manager.lock(&mocked(&[3]), 1, account).await.unwrap();
+            let nonces = [bundle.reserve(), bundle.reserve()];
+
+            let public = {
+                let manager = manager.clone();
+                // the bundle's nonces were never used on chain
+                tokio::spawn(async move { manager.reserve(&mocked(&[3]), 1, account).await })
+            };
+
+            // the bundle never landed
+            for nonce in nonces {
+                bundle.sent(nonce);
+            }
+            bundle.resync();
+            drop(bundle);
+            assert_eq!(public.await.unwrap(), Ok(U256::from(3)));
+        }
+
+        #[test]
+        fn keeps_the_counter_while_reservations_are_in_flight() {
+            let mut nonces = AccountNonces::default();
+            nonces.sync(U256::from(3), 1, Address::zero());
+            assert_eq!(nonces.take(), U256::from(3));
+            assert_eq!(nonces.take(), U256::from(4));
+            nonces.reserved.remove(&U256::from(3));
+            nonces.stale = true;
+
+            nonces.sync(U256::from(3), 1, Address::zero());
+            assert!(nonces.needs_sync());
+            assert_eq!(nonces.take(), U256::from(5));
+        }
+    }
+}
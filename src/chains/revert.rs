@@ -0,0 +1,172 @@
+pub mod revert {
+    use crate::chains::ethereum::ethereum_chain::ESCROW_ABI;
+    use crate::chains::executor::solver_executor::SOLVEREXECUTOR_ABI;
+    use ethers::abi::{decode, Abi, ParamType, Token};
+    use ethers::prelude::*;
+    use std::fmt;
+
+    /// `Error(string)`, from `require` and `revert("...")`
+    const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+    /// `Panic(uint256)`, from failed asserts, overflows, ...
+    const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+    /// Why a call reverted, decoded from its revert data.
+    #[derive(Debug, Clone)]
+    pub enum Revert {
+        Reason(String),
+        Panic(U256),
+        /// custom error of a contract the solver talks to
+        Custom { name: String, args: Vec<Token> },
+        /// revert data matching nothing known, empty for a bare `revert()`
+        Unknown(Bytes),
+    }
+
+    impl fmt::Display for Revert {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Revert::Reason(reason) => write!(f, "{}", reason),
+                Revert::Panic(code) => write!(f, "panic {:#x} ({})", code, panic_reason(*code)),
+                Revert::Custom { name, args } => {
+                    let args: Vec<String> = args.iter().map(display_arg).collect();
+                    write!(f, "{}({})", name, args.join(", "))
+                }
+                Revert::Unknown(data) if data.is_empty() => write!(f, "no revert data"),
+                Revert::Unknown(data) => write!(f, "unknown revert data {}", data),
+            }
+        }
+    }
+
+    fn panic_reason(code: U256) -> &'static str {
+        match code.low_u64() {
+            0x01 => "assertion failed",
+            0x11 => "arithmetic overflow or underflow",
+            0x12 => "division by zero",
+            0x21 => "invalid enum value",
+            0x31 => "pop on empty array",
+            0x32 => "array index out of bounds",
+            0x41 => "out of memory",
+            0x51 => "call to uninitialized function",
+            _ => "unknown panic",
+        }
+    }
+
+    fn display_arg(arg: &Token) -> String {
+        match arg {
+            // nested revert data, e.g. the executor's SwapFailed(target, reason)
+            Token::Bytes(data) => decode_revert(data).to_string(),
+            Token::Address(address) => format!("{:?}", address),
+            Token::FixedBytes(bytes) => format!("0x{}", hex::encode(bytes)),
+            arg => arg.to_string(),
+        }
+    }
+
+    fn decode_custom(abi: &Abi, data: &[u8]) -> Option<Revert> {
+        abi.errors()
+            .find(|error| error.signature()[..4] == data[..4])
+            .and_then(|error| {
+                let args = error.decode(&data[4..]).ok()?;
+                Some(Revert::Custom {
+                    name: error.name.clone(),
+                    args,
+                })
+            })
+    }
+
+    /// Decodes revert data as `Error(string)`, `Panic(uint256)` or a custom
+    /// error of the escrow or the solver executor.
+    pub fn decode_revert(data: &[u8]) -> Revert {
+        if data.len() < 4 {
+            return Revert::Unknown(Bytes::from(data.to_vec()));
+        }
+
+        let (selector, args) = data.split_at(4);
+        if selector == ERROR_SELECTOR {
+            if let Ok(Some(Token::String(reason))) =
+                decode(&[ParamType::String], args).map(|tokens| tokens.into_iter().next())
+            {
+                return Revert::Reason(reason);
+            }
+        }
+        if selector == PANIC_SELECTOR {
+            if let Ok(Some(Token::Uint(code))) =
+                decode(&[ParamType::Uint(256)], args).map(|tokens| tokens.into_iter().next())
+            {
+                return Revert::Panic(code);
+            }
+        }
+
+        [&*ESCROW_ABI, &*SOLVEREXECUTOR_ABI]
+            .into_iter()
+            .find_map(|abi| decode_custom(abi, data))
+            .unwrap_or_else(|| Revert::Unknown(Bytes::from(data.to_vec())))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ethers::abi::encode;
+
+        fn with_selector(selector: [u8; 4], args: &[Token]) -> Vec<u8> {
+            [selector.to_vec(), encode(args)].concat()
+        }
+
+        fn error_selector(abi: &Abi, name: &str) -> [u8; 4] {
+            let error = abi.errors().find(|error| error.name == name).unwrap();
+            error.signature()[..4].try_into().unwrap()
+        }
+
+        #[test]
+        fn decodes_error_string() {
+            let data = with_selector(ERROR_SELECTOR, &[Token::String("STF".to_string())]);
+            assert!(matches!(decode_revert(&data), Revert::Reason(reason) if reason == "STF"));
+        }
+
+        #[test]
+        fn decodes_panic_code() {
+            let data = with_selector(PANIC_SELECTOR, &[Token::Uint(U256::from(0x11))]);
+            let revert = decode_revert(&data);
+            assert!(matches!(revert, Revert::Panic(code) if code == U256::from(0x11)));
+            assert_eq!(
+                revert.to_string(),
+                "panic 0x11 (arithmetic overflow or underflow)"
+            );
+        }
+
+        #[test]
+        fn decodes_escrow_custom_error() {
+            let data = with_selector(
+                error_selector(&ESCROW_ABI, "IntentNotFound"),
+                &[Token::String("42".to_string())],
+            );
+            let revert = decode_revert(&data);
+            assert!(matches!(&revert, Revert::Custom { name, .. } if name == "IntentNotFound"));
+            assert_eq!(revert.to_string(), "IntentNotFound(42)");
+        }
+
+        #[test]
+        fn decodes_nested_executor_revert() {
+            let target = Address::repeat_byte(0x11);
+            let reason = with_selector(
+                ERROR_SELECTOR,
+                &[Token::String("Too little received".to_string())],
+            );
+            let data = with_selector(
+                error_selector(&SOLVEREXECUTOR_ABI, "SwapFailed"),
+                &[Token::Address(target), Token::Bytes(reason)],
+            );
+            assert_eq!(
+                decode_revert(&data).to_string(),
+                format!("SwapFailed({:?}, Too little received)", target)
+            );
+        }
+
+        #[test]
+        fn keeps_unknown_revert_data() {
+            assert_eq!(decode_revert(&[]).to_string(), "no revert data");
+            let data = [0xde, 0xad, 0xbe, 0xef, 0x00];
+            assert!(
+                matches!(decode_revert(&data), Revert::Unknown(bytes) if bytes.as_ref() == data)
+            );
+        }
+    }
+}
@@ -1,29 +1,55 @@
 pub mod solana_chain {
     use crate::chains::*;
-    use crate::routers::jupiter::create_token_account;
-    use crate::routers::jupiter::jupiter_swap;
-    use crate::routers::jupiter::quote;
-    use crate::routers::jupiter::Memo as Jup_Memo;
-    use crate::routers::jupiter::QuoteConfig;
-    use crate::routers::jupiter::SwapMode;
+    use crate::routers::jupiter::{
+        create_token_account, jupiter_quote, JupiterRouter, SwapRequest, JUPITER_CLIENT,
+    };
+    use crate::routers::swap_router::{
+        select_router, SwapParams, SwapRouter, SwapSide, SwapTransaction,
+    };
+    use crate::routers::QuoteResult;
     use crate::PostIntentInfo;
-    use anchor_client::Cluster;
+    use anchor_lang::{InstructionData, ToAccountMetas};
+    use lazy_static::lazy_static;
     use num_bigint::BigInt;
+    use num_traits::ToPrimitive;
     use serde::{Deserialize, Serialize};
     use serde_json::json;
     use solana_client::nonblocking::rpc_client::RpcClient;
     use solana_client::rpc_config::RpcSendTransactionConfig;
+    use solana_sdk::address_lookup_table::state::AddressLookupTable;
+    use solana_sdk::address_lookup_table_account::AddressLookupTableAccount;
     use solana_sdk::commitment_config::CommitmentConfig;
     use solana_sdk::compute_budget::ComputeBudgetInstruction;
+    use solana_sdk::instruction::Instruction;
+    use solana_sdk::message::{v0, VersionedMessage};
+    use solana_sdk::packet::PACKET_DATA_SIZE;
+    use solana_sdk::program_pack::Pack;
     use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::hash::Hash;
     use solana_sdk::signature::{Keypair, Signer};
-    use solana_sdk::transaction::Transaction;
+    use solana_sdk::transaction::{Transaction, VersionedTransaction};
     use spl_associated_token_account::get_associated_token_address;
+    use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
     use spl_token::instruction::transfer;
+    use std::collections::HashMap;
     use std::env;
     use std::str::FromStr;
     use std::sync::Arc;
     use std::time::Duration;
+    use tokio::sync::RwLock;
+
+    lazy_static! {
+        // <mint, decimals>, mint decimals never change so they are cached forever
+        static ref SOLANA_TOKEN_DECIMALS: Arc<RwLock<HashMap<String, u8>>> =
+            Arc::new(RwLock::new(HashMap::new()));
+    }
+
+    /// Compute-unit limit requested by `solana_send_funds_to_user`.
+    pub const SEND_FUNDS_TO_USER_COMPUTE_UNITS: u32 = 1_000_000;
+    /// Compute-unit limit of the atomic Jupiter swap + sendFundsToUser transaction.
+    pub const ATOMIC_SWAP_COMPUTE_UNITS: u32 = 1_400_000;
+    /// Base fee charged by the network for every transaction signature.
+    pub const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
 
     #[derive(Debug, Serialize, Deserialize)]
     struct SwapData {
@@ -45,6 +71,7 @@ pub mod solana_chain {
         intent: &PostIntentInfo,
         intent_id: &str,
         amount: &str,
+        quote: &QuoteResult,
     ) -> Result<(), String> {
         let from_keypair = Keypair::from_base58_string(
             env::var("SOLANA_KEYPAIR")
@@ -82,35 +109,62 @@ pub mod solana_chain {
             amount_in = transfer_input.amount_in.clone();
         }
 
-        // swap USDT -> token_out
-        if !token_out.eq_ignore_ascii_case(usdt_contract_address) {
-            if let Err(e) = solana_transfer_swap(intent.clone(), amount).await {
-                return Err(format!(
-                    "Error occurred on Solana swap USDT -> token_out (manual swap required): {}",
-                    e
-                ));
-            }
-        }
+        let solver_out = get_solver_address(&intent.src_chain)
+            .expect("chain not supported, this should't happen");
 
-        let solver_out = if intent.src_chain == "ethereum" {
-            SOLVER_ADDRESSES.get(0).unwrap()
-        } else if intent.src_chain == "solana" {
-            SOLVER_ADDRESSES.get(1).unwrap()
+        let send_funds = if intent.function_name == "swap"
+            && !token_out.eq_ignore_ascii_case(usdt_contract_address)
+        {
+            // swap USDT -> token_out & solver -> token_out -> user | user -> token_in -> solver
+            // in a single transaction
+            let swap_params = SwapParams {
+                token_in: usdt_contract_address.to_string(),
+                token_out: token_out.clone(),
+                amount: BigInt::from_str(amount).map_err(|e| format!("Invalid amount: {}", e))?,
+                side: SwapSide::ExactOut,
+                sender: from_keypair.pubkey().to_string(),
+                receiver: from_keypair.pubkey().to_string(),
+                slippage_bps: 100,
+            };
+
+            solana_swap_and_send_funds_to_user(
+                intent_id,
+                &token_in,
+                &token_out,
+                &user_account,
+                solver_out.to_string(),
+                intent.src_chain == intent.dst_chain,
+                &swap_params,
+                quote.dst_router,
+                &quote.bridge_amount,
+            )
+            .await
         } else {
-            panic!("chain not supported, this should't happen");
+            // swap USDT -> token_out
+            if !token_out.eq_ignore_ascii_case(usdt_contract_address) {
+                if let Err(e) =
+                    solana_transfer_swap(intent.clone(), amount, quote.dst_router).await
+                {
+                    return Err(format!(
+                        "Error occurred on Solana swap USDT -> token_out (manual swap required): {}",
+                        e
+                    ));
+                }
+            }
+
+            // solver -> token_out -> user | user -> token_in -> solver
+            solana_send_funds_to_user(
+                intent_id,
+                &token_in,
+                &token_out,
+                &user_account,
+                solver_out.to_string(),
+                intent.src_chain == intent.dst_chain,
+            )
+            .await
         };
 
-        // solver -> token_out -> user | user -> token_in -> solver
-        if let Err(e) = solana_send_funds_to_user(
-            intent_id,
-            &token_in,
-            &token_out,
-            &user_account,
-            solver_out.to_string(),
-            intent.src_chain == intent.dst_chain,
-        )
-        .await
-        {
+        if let Err(e) = send_funds {
             return Err(format!(
                 "Error occurred on send token_out -> user & user sends token_in -> solver: {}",
                 e
@@ -119,16 +173,20 @@ pub mod solana_chain {
         } else if intent.src_chain == intent.dst_chain
             && !token_in.eq_ignore_ascii_case(usdt_contract_address)
         {
-            let memo = format!(
-                r#"{{"user_account": "{}","token_in": "{}","token_out": "{}","amount": {},"slippage_bps": {}}}"#,
-                SOLVER_ADDRESSES.get(1).unwrap(),
-                token_in,
-                usdt_contract_address,
-                amount_in,
-                100
-            );
+            let swap_params = SwapParams {
+                token_in: token_in.clone(),
+                token_out: usdt_contract_address.to_string(),
+                amount: BigInt::from_str(&amount_in)
+                    .map_err(|e| format!("Invalid amount_in: {}", e))?,
+                side: SwapSide::ExactIn,
+                sender: from_keypair.pubkey().to_string(),
+                receiver: SOLVER_ADDRESSES.get(1).unwrap().to_string(),
+                slippage_bps: 100,
+            };
 
-            if let Err(e) = jupiter_swap(&memo, &client, &from_keypair, SwapMode::ExactIn).await {
+            if let Err(e) =
+                solana_swap(&swap_params, quote.src_router, &client, &from_keypair, None).await
+            {
                 return Err(format!("Error on Solana swap token_in -> USDT: {e}"));
             }
         } else {
@@ -160,20 +218,25 @@ pub mod solana_chain {
             };
 
             println!(
-                "You have {} {} USDT on intent {intent_id}",
+                "You have {} {} USDT on intent {intent_id} (quoted commission {} USDT)",
                 if balance_post >= balance_ant {
                     "won"
                 } else {
                     "lost"
                 },
-                balance
+                balance,
+                quote.commission.to_f64().unwrap_or_default() / 1e6
             );
         }
 
         Ok(())
     }
 
-    pub async fn solana_transfer_swap(intent: PostIntentInfo, amount: &str) -> Result<(), String> {
+    pub async fn solana_transfer_swap(
+        intent: PostIntentInfo,
+        amount: &str,
+        router: Option<&str>,
+    ) -> Result<(), String> {
         let rpc_url = env::var("SOLANA_RPC").map_err(|_| "SOLANA_RPC must be set".to_string())?;
 
         let from_keypair_str =
@@ -216,15 +279,18 @@ pub mod solana_chain {
                     token_out = transfer_output.token_out.clone();
                 }
 
-                let memo = format!(
-                    r#"{{"user_account": "{}","token_in": "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB","token_out": "{}","amount": {},"slippage_bps": {}}}"#,
-                    SOLVER_ADDRESSES.get(1).unwrap(),
+                let swap_params = SwapParams {
+                    token_in: "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB".to_string(),
                     token_out,
-                    amount,
-                    100
-                );
-
-                jupiter_swap(&memo, &client, &from_keypair, SwapMode::ExactOut)
+                    amount: BigInt::from_str(amount)
+                        .map_err(|e| format!("Invalid amount: {}", e))?,
+                    side: SwapSide::ExactOut,
+                    sender: from_keypair.pubkey().to_string(),
+                    receiver: SOLVER_ADDRESSES.get(1).unwrap().to_string(),
+                    slippage_bps: 100,
+                };
+
+                solana_swap(&swap_params, router, &client, &from_keypair, None)
                     .await
                     .map_err(|err| format!("Swap failed: {}", err))?;
             }
@@ -310,6 +376,10 @@ pub mod solana_chain {
     pub async fn _get_solana_token_decimals(
         token_address: &str,
     ) -> Result<u8, Box<dyn std::error::Error>> {
+        if let Some(decimals) = SOLANA_TOKEN_DECIMALS.read().await.get(token_address) {
+            return Ok(*decimals);
+        }
+
         let rpc_url = env::var("SOLANA_RPC").expect("SOLANA_RPC must be set");
         let client = reqwest::Client::new();
         let request_body = json!({
@@ -330,245 +400,595 @@ pub mod solana_chain {
             .await?;
 
         if let Some(decimals) = response["result"]["value"]["decimals"].as_u64() {
+            let mut cache = SOLANA_TOKEN_DECIMALS.write().await;
+            cache.insert(token_address.to_string(), decimals as u8);
+            drop(cache);
             Ok(decimals as u8)
         } else {
             Err("Token information not available.".into())
         }
     }
 
-    pub async fn solana_simulate_swap(
-        dst_chain_user: &str,
-        token_in: &str,
-        token_out: &str,
-        amount_in: u64,
-    ) -> String {
-        let memo_json = json!({
-            "user_account": dst_chain_user,
-            "token_in": token_in,
-            "token_out": token_out,
-            "amount": amount_in,
-            "slippage_bps": 100
-        });
+    /// Median of the recent prioritization fees paid to write-lock the escrow
+    /// program, in micro-lamports per compute unit.
+    pub async fn fetch_solana_priority_fee() -> Result<u64, Box<dyn std::error::Error>> {
+        let rpc_url = env::var("SOLANA_RPC").expect("SOLANA_RPC must be set");
+        let client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
 
-        let memo = match Jup_Memo::from_json(&memo_json.to_string()) {
-            Ok(memo) => memo,
-            Err(_) => return "0".to_string(),
-        };
+        let mut fees: Vec<u64> = client
+            .get_recent_prioritization_fees(&[bridge_escrow::ID])
+            .await
+            .map_err(|e| format!("Failed to fetch prioritization fees: {}", e))?
+            .into_iter()
+            .map(|fee| fee.prioritization_fee)
+            .collect();
 
-        let quote_config = QuoteConfig {
-            only_direct_routes: false,
-            swap_mode: Some(SwapMode::ExactIn),
-            slippage_bps: Some(memo.slippage_bps),
-            ..QuoteConfig::default()
-        };
+        if fees.is_empty() {
+            return Ok(0);
+        }
+        fees.sort_unstable();
+        Ok(fees[fees.len() / 2])
+    }
 
-        let quotes = match quote(memo.token_in, memo.token_out, memo.amount, quote_config).await {
-            Ok(quotes) => quotes,
-            Err(_) => return "0".to_string(),
-        };
+    /// Lamports paid for a single-signature transaction with the given
+    /// compute-unit limit and compute-unit price.
+    pub fn solana_tx_fee(compute_units: u32, micro_lamports_per_cu: u64) -> u64 {
+        LAMPORTS_PER_SIGNATURE + (compute_units as u64 * micro_lamports_per_cu) / 1_000_000
+    }
 
-        BigInt::from(quotes.out_amount).to_string()
+    /// Rent-exempt balance of a new SPL token account, in lamports.
+    pub async fn fetch_token_account_rent() -> Result<u64, Box<dyn std::error::Error>> {
+        let rpc_url = env::var("SOLANA_RPC").expect("SOLANA_RPC must be set");
+        let client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+
+        let rent = client
+            .get_minimum_balance_for_rent_exemption(spl_token::state::Account::LEN)
+            .await
+            .map_err(|e| format!("Failed to fetch rent exemption: {}", e))?;
+        Ok(rent)
     }
 
-    pub async fn solana_send_funds_to_user(
-        intent_id: &str,
-        token_in_mint: &str,
-        token_out_mint: &str,
-        user: &str,
-        solver_out: String,
-        single_domain: bool,
-    ) -> Result<(), String> {
-        // Load the keypair from environment variable
-        let solana_keypair = env::var("SOLANA_KEYPAIR")
-            .map_err(|e| format!("Failed to read SOLANA_KEYPAIR from environment: {}", e))?;
+    /// Counts the associated token accounts in `owners_and_mints` that do not
+    /// exist yet and will be created (and paid for) by the solver.
+    pub async fn count_missing_token_accounts(
+        owners_and_mints: &[(&str, &str)],
+    ) -> Result<u32, Box<dyn std::error::Error>> {
+        let rpc_url = env::var("SOLANA_RPC").expect("SOLANA_RPC must be set");
+        let client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
 
-        let solver = Arc::new(Keypair::from_base58_string(&solana_keypair));
+        let mut addresses = Vec::with_capacity(owners_and_mints.len());
+        for (owner, mint) in owners_and_mints {
+            let address =
+                get_associated_token_address(&Pubkey::from_str(owner)?, &Pubkey::from_str(mint)?);
+            if !addresses.contains(&address) {
+                addresses.push(address);
+            }
+        }
 
-        // Clone the necessary variables for the task
-        let solver_clone = Arc::clone(&solver);
-        let intent_id = intent_id.to_string();
-        let token_in_mint = token_in_mint.to_string();
-        let token_out_mint = token_out_mint.to_string();
-        let user = user.to_string();
+        let accounts = client
+            .get_multiple_accounts(&addresses)
+            .await
+            .map_err(|e| format!("Failed to fetch token accounts: {}", e))?;
+
+        Ok(accounts.iter().filter(|account| account.is_none()).count() as u32)
+    }
 
+    pub async fn solana_token_balance(
+        token_mint: &str,
+        owner: &str,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
         let rpc_url = env::var("SOLANA_RPC").expect("SOLANA_RPC must be set");
-        let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
-        let solver_token_in_addr = get_associated_token_address(
-            &solver_clone.pubkey(),
-            &Pubkey::from_str(&token_in_mint).unwrap(),
-        );
+        let client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
 
+        let token_account =
+            get_associated_token_address(&Pubkey::from_str(owner)?, &Pubkey::from_str(token_mint)?);
+        let balance = client
+            .get_token_account_balance(&token_account)
+            .await
+            .map_err(|e| format!("Failed to get token account balance: {}", e))?;
+        Ok(balance.amount.parse::<u64>()?)
+    }
+
+    /// Swaps through `router`, aborting before sending when the router
+    /// expects to spend more than `max_amount_in`.
+    pub async fn solana_swap(
+        params: &SwapParams,
+        router: Option<&str>,
+        rpc_client: &RpcClient,
+        keypair: &Keypair,
+        max_amount_in: Option<&BigInt>,
+    ) -> Result<(), String> {
+        let router = select_router("solana", router)?;
+
+        if let Some(max_amount_in) = max_amount_in {
+            let amount_in = router.quote(params).await?.amount_in;
+            if &amount_in > max_amount_in {
+                return Err(format!(
+                    "{} expects to spend {} token_in, above the {} budgeted",
+                    router.name(),
+                    amount_in,
+                    max_amount_in
+                ));
+            }
+        }
+
+        let receiver = Pubkey::from_str(&params.receiver)
+            .map_err(|e| format!("Invalid receiver pubkey: {}", e))?;
+        let token_out = Pubkey::from_str(&params.token_out)
+            .map_err(|e| format!("Invalid token_out pubkey: {}", e))?;
+        let receiver_token_out = get_associated_token_address(&receiver, &token_out);
+
+        // Check if the receiver token account exists, and create it if necessary
         if rpc_client
-            .get_token_account_balance(&solver_token_in_addr)
+            .get_token_account_balance(&receiver_token_out)
             .await
             .is_err()
         {
-            if let Err(e) = create_token_account(
-                &solver_clone.pubkey(),
-                &Pubkey::from_str(&token_in_mint).unwrap(),
-                &solver,
-                &rpc_client,
-            )
+            create_token_account(&receiver, &token_out, keypair, rpc_client)
+                .await
+                .map_err(|e| format!("Failed to create token account: {}", e))?;
+        }
+
+        let swap_transaction = match router.build_swap(params).await? {
+            SwapTransaction::Solana(swap_transaction) => swap_transaction,
+            _ => return Err(format!("{} did not build a Solana transaction", router.name())),
+        };
+
+        // Get the latest blockhash
+        let mut message = swap_transaction.message;
+        let recent_blockhash: Hash = rpc_client
+            .get_latest_blockhash()
+            .await
+            .map_err(|e| format!("Failed to get latest blockhash: {}", e))?;
+        message.set_recent_blockhash(recent_blockhash);
+
+        // Sign the swap transaction
+        let swap_transaction = VersionedTransaction::try_new(message, &[keypair])
+            .map_err(|e| format!("Failed to create signed transaction: {}", e))?;
+
+        // Simulate the transaction before sending
+        let simulation = rpc_client
+            .simulate_transaction(&swap_transaction)
+            .await
+            .map_err(|e| format!("Transaction simulation failed: {}", e))?;
+        if let Some(err) = simulation.value.err {
+            return Err(format!("Transaction simulation failed: {}", err));
+        }
+
+        // Send and confirm the transaction
+        rpc_client
+            .send_and_confirm_transaction_with_spinner(&swap_transaction)
             .await
+            .map_err(|e| format!("Transaction failed: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Creates the solver's associated token account for `mint` if it is missing,
+    /// the escrow pays token_in into it on single domain intents.
+    async fn ensure_solver_token_account(
+        rpc_client: &RpcClient,
+        solver: &Keypair,
+        mint: &Pubkey,
+    ) {
+        let solver_token_addr = get_associated_token_address(&solver.pubkey(), mint);
+
+        if rpc_client
+            .get_token_account_balance(&solver_token_addr)
+            .await
+            .is_err()
+        {
+            if let Err(e) =
+                create_token_account(&solver.pubkey(), mint, solver, rpc_client).await
             {
                 eprintln!("Failed to create token account: {}", e);
             }
         }
+    }
 
-        // Spawn a blocking task to execute the transaction
-        tokio::task::spawn_blocking(move || {
-            let client = anchor_client::Client::new_with_options(
-                Cluster::Mainnet,
-                solver_clone.clone(),
-                CommitmentConfig::processed(),
-            );
+    /// `bridge_escrow` SendFundsToUser instruction: solver -> token_out -> user,
+    /// and user -> token_in -> solver on single domain intents.
+    fn send_funds_to_user_instruction(
+        solver: &Pubkey,
+        intent_id: &str,
+        token_in_mint: &str,
+        token_out_mint: &str,
+        user: &str,
+        solver_out: String,
+        single_domain: bool,
+    ) -> Result<Instruction, String> {
+        let user = Pubkey::from_str(user).map_err(|e| format!("Invalid user pubkey: {}", e))?;
+        let token_out = Pubkey::from_str(token_out_mint)
+            .map_err(|e| format!("Invalid token_out_mint pubkey: {}", e))?;
 
-            let program = client
-                .program(bridge_escrow::ID)
-                .map_err(|e| format!("Failed to access bridge_escrow program: {}", e))?;
+        let user_token_out_addr = get_associated_token_address(&user, &token_out);
 
-            let user_token_out_addr = get_associated_token_address(
-                &Pubkey::from_str(&user).map_err(|e| format!("Invalid user pubkey: {}", e))?,
-                &Pubkey::from_str(&token_out_mint)
-                    .map_err(|e| format!("Invalid token_out_mint pubkey: {}", e))?,
-            );
+        let intent_state =
+            Pubkey::find_program_address(&[b"intent", intent_id.as_bytes()], &bridge_escrow::ID).0;
 
-            let intent_state = Pubkey::find_program_address(
-                &[b"intent", intent_id.as_bytes()],
-                &bridge_escrow::ID,
-            )
-            .0;
+        let auctioneer_state = Pubkey::find_program_address(&[b"auctioneer"], &bridge_escrow::ID).0;
 
-            let auctioneer_state =
-                Pubkey::find_program_address(&[b"auctioneer"], &bridge_escrow::ID).0;
+        let solver_token_out_addr = get_associated_token_address(solver, &token_out);
 
-            let solver_token_out_addr = get_associated_token_address(
-                &solver_clone.pubkey(),
-                &Pubkey::from_str(&token_out_mint)
-                    .map_err(|e| format!("Invalid token_out_mint pubkey: {}", e))?,
-            );
+        let solana_ibc_id = Pubkey::from_str("2HLLVco5HvwWriNbUhmVwA2pCetRkpgrqwnjcsZdyTKT").unwrap();
 
-            let solana_ibc_id =
-                Pubkey::from_str("2HLLVco5HvwWriNbUhmVwA2pCetRkpgrqwnjcsZdyTKT").unwrap();
+        let (_storage, _bump_storage) =
+            Pubkey::find_program_address(&[solana_ibc::SOLANA_IBC_STORAGE_SEED], &solana_ibc_id);
 
-            let (_storage, _bump_storage) = Pubkey::find_program_address(
-                &[solana_ibc::SOLANA_IBC_STORAGE_SEED],
-                &solana_ibc_id,
-            );
+        let (_trie, _bump_trie) =
+            Pubkey::find_program_address(&[solana_ibc::TRIE_SEED], &solana_ibc_id);
 
-            let (_trie, _bump_trie) =
-                Pubkey::find_program_address(&[solana_ibc::TRIE_SEED], &solana_ibc_id);
+        let (_chain, _bump_chain) =
+            Pubkey::find_program_address(&[solana_ibc::CHAIN_SEED], &solana_ibc_id);
 
-            let (_chain, _bump_chain) =
-                Pubkey::find_program_address(&[solana_ibc::CHAIN_SEED], &solana_ibc_id);
+        let (_mint_authority, _bump_mint_authority) =
+            Pubkey::find_program_address(&[solana_ibc::MINT_ESCROW_SEED], &solana_ibc_id);
 
-            let (_mint_authority, _bump_mint_authority) =
-                Pubkey::find_program_address(&[solana_ibc::MINT_ESCROW_SEED], &solana_ibc_id);
+        let _dummy_token_mint = Pubkey::find_program_address(&[b"dummy"], &bridge_escrow::ID).0;
 
-            let _dummy_token_mint = Pubkey::find_program_address(&[b"dummy"], &bridge_escrow::ID).0;
+        let _hashed_full_denom =
+            lib::hash::CryptoHash::digest(&_dummy_token_mint.to_string().as_bytes());
 
-            let _hashed_full_denom =
-                lib::hash::CryptoHash::digest(&_dummy_token_mint.to_string().as_bytes());
+        let (_escrow_account, _bump_escrow_account) = Pubkey::find_program_address(
+            &[solana_ibc::ESCROW, &_hashed_full_denom.as_slice()],
+            &solana_ibc_id,
+        );
 
-            let (_escrow_account, _bump_escrow_account) = Pubkey::find_program_address(
-                &[solana_ibc::ESCROW, &_hashed_full_denom.as_slice()],
-                &solana_ibc_id,
-            );
+        let _receiver_token_account = get_associated_token_address(solver, &_dummy_token_mint);
+
+        let (_fee_collector, _bump_fee_collector) =
+            Pubkey::find_program_address(&[solana_ibc::FEE_SEED], &solana_ibc_id);
+
+        let auctioneer_token_in_account;
+        let solver_token_in_account;
+        let token_in;
+        let mut ibc_program = None;
+        let mut receiver = None;
+        let mut storage = None;
+        let mut trie = None;
+        let mut chain = None;
+        let mut mint_authority = None;
+        let mut dummy_token_mint = None;
+        let mut escrow_account = None;
+        let mut receiver_token_account = None;
+        let mut fee_collector = None;
+        let mut hashed_full_denom = None;
+
+        if !single_domain {
+            token_in = None;
+            auctioneer_token_in_account = None;
+            solver_token_in_account = None;
+            ibc_program = Some(solana_ibc_id);
+            receiver = Some(user);
+            storage = Some(_storage);
+            trie = Some(_trie);
+            chain = Some(_chain);
+            mint_authority = Some(_mint_authority);
+            dummy_token_mint = Some(_dummy_token_mint);
+            escrow_account = Some(_escrow_account);
+            receiver_token_account = Some(_receiver_token_account);
+            fee_collector = Some(_fee_collector);
+            hashed_full_denom = Some(_hashed_full_denom);
+        } else {
+            let token_in_mint = Pubkey::from_str(token_in_mint)
+                .map_err(|e| format!("Invalid token_in_mint pubkey: {}", e))?;
+            token_in = Some(token_in_mint);
+            auctioneer_token_in_account =
+                Some(get_associated_token_address(&auctioneer_state, &token_in_mint));
+            solver_token_in_account = Some(get_associated_token_address(solver, &token_in_mint));
+        }
 
-            let _receiver_token_account =
-                get_associated_token_address(&solver.pubkey(), &_dummy_token_mint);
-
-            let (_fee_collector, _bump_fee_collector) =
-                Pubkey::find_program_address(&[solana_ibc::FEE_SEED], &solana_ibc_id);
-
-            let auctioneer_token_in_account;
-            let solver_token_in_account;
-            let token_in;
-            let mut ibc_program = None;
-            let mut receiver = None;
-            let mut storage = None;
-            let mut trie = None;
-            let mut chain = None;
-            let mut mint_authority = None;
-            let mut dummy_token_mint = None;
-            let mut escrow_account = None;
-            let mut receiver_token_account = None;
-            let mut fee_collector = None;
-            let mut hashed_full_denom = None;
-
-            if !single_domain {
-                token_in = None;
-                auctioneer_token_in_account = None;
-                solver_token_in_account = None;
-                ibc_program = Some(solana_ibc_id);
-                receiver = Some(
-                    Pubkey::from_str(&user).map_err(|e| format!("Invalid user pubkey: {}", e))?,
-                );
-                storage = Some(_storage);
-                trie = Some(_trie);
-                chain = Some(_chain);
-                mint_authority = Some(_mint_authority);
-                dummy_token_mint = Some(_dummy_token_mint);
-                escrow_account = Some(_escrow_account);
-                receiver_token_account = Some(_receiver_token_account);
-                fee_collector = Some(_fee_collector);
-                hashed_full_denom = Some(_hashed_full_denom);
-            } else {
-                token_in = Some(Pubkey::from_str(&token_in_mint).unwrap());
+        let accounts = bridge_escrow::accounts::SplTokenTransfer {
+            intent: intent_state,
+            auctioneer_state,
+            solver: *solver,
+            auctioneer: Pubkey::from_str("5zCZ3jk8EZnJyG7fhDqD6tmqiYTLZjik5HUpGMnHrZfC")
+                .map_err(|e| format!("Invalid auctioneer pubkey: {}", e))?,
+            token_in,
+            token_out,
+            auctioneer_token_in_account,
+            solver_token_in_account,
+            solver_token_out_account: solver_token_out_addr,
+            user_token_out_account: user_token_out_addr,
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: anchor_lang::solana_program::system_program::ID,
+            ibc_program,
+            receiver,
+            storage,
+            trie,
+            chain,
+            mint_authority,
+            token_mint: dummy_token_mint,
+            escrow_account,
+            receiver_token_account,
+            fee_collector,
+        };
+        let args = bridge_escrow::instruction::SendFundsToUser {
+            intent_id: intent_id.to_string(),
+            hashed_full_denom,
+            solver_out: Some(solver_out),
+        };
 
-                let token_in_escrow_addr =
-                    get_associated_token_address(&auctioneer_state, &token_in.unwrap());
+        Ok(Instruction {
+            program_id: bridge_escrow::ID,
+            accounts: accounts.to_account_metas(None),
+            data: args.data(),
+        })
+    }
 
-                auctioneer_token_in_account = Some(token_in_escrow_addr);
-                solver_token_in_account = Some(solver_token_in_addr);
-            }
+    pub async fn solana_send_funds_to_user(
+        intent_id: &str,
+        token_in_mint: &str,
+        token_out_mint: &str,
+        user: &str,
+        solver_out: String,
+        single_domain: bool,
+    ) -> Result<(), String> {
+        // Load the keypair from environment variable
+        let solana_keypair = env::var("SOLANA_KEYPAIR")
+            .map_err(|e| format!("Failed to read SOLANA_KEYPAIR from environment: {}", e))?;
+        let solver = Keypair::from_base58_string(&solana_keypair);
 
-            program
-                .request()
-                .instruction(ComputeBudgetInstruction::set_compute_unit_limit(1_000_000))
-                .instruction(ComputeBudgetInstruction::request_heap_frame(128 * 1024))
-                .accounts(bridge_escrow::accounts::SplTokenTransfer {
-                    intent: intent_state,
-                    auctioneer_state,
-                    solver: solver_clone.pubkey(),
-                    auctioneer: Pubkey::from_str("5zCZ3jk8EZnJyG7fhDqD6tmqiYTLZjik5HUpGMnHrZfC")
-                        .map_err(|e| format!("Invalid auctioneer pubkey: {}", e))?,
-                    token_in: token_in,
-                    token_out: Pubkey::from_str(&token_out_mint)
-                        .map_err(|e| format!("Invalid token_out_mint pubkey: {}", e))?,
-                    auctioneer_token_in_account: auctioneer_token_in_account,
-                    solver_token_in_account: solver_token_in_account,
-                    solver_token_out_account: solver_token_out_addr,
-                    user_token_out_account: user_token_out_addr,
-                    token_program: anchor_spl::token::ID,
-                    associated_token_program: anchor_spl::associated_token::ID,
-                    system_program: anchor_lang::solana_program::system_program::ID,
-                    ibc_program: ibc_program,
-                    receiver: receiver,
-                    storage: storage,
-                    trie: trie,
-                    chain: chain,
-                    mint_authority: mint_authority,
-                    token_mint: dummy_token_mint,
-                    escrow_account: escrow_account,
-                    receiver_token_account: receiver_token_account,
-                    fee_collector: fee_collector,
-                })
-                .args(bridge_escrow::instruction::SendFundsToUser {
-                    intent_id: intent_id.to_string(),
-                    hashed_full_denom: hashed_full_denom,
-                    solver_out: Some(solver_out),
-                })
-                .payer(solver_clone.clone())
-                .signer(&*solver_clone)
-                .send_with_spinner_and_config(RpcSendTransactionConfig {
+        let rpc_url = env::var("SOLANA_RPC").expect("SOLANA_RPC must be set");
+        let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+
+        let token_in = Pubkey::from_str(token_in_mint)
+            .map_err(|e| format!("Invalid token_in_mint pubkey: {}", e))?;
+        ensure_solver_token_account(&rpc_client, &solver, &token_in).await;
+
+        // pay the priority the flat fee charged the user for
+        let priority_fee = fetch_solana_priority_fee()
+            .await
+            .map_err(|e| format!("Failed to fetch priority fee: {}", e))?;
+
+        let instructions = [
+            ComputeBudgetInstruction::set_compute_unit_limit(SEND_FUNDS_TO_USER_COMPUTE_UNITS),
+            ComputeBudgetInstruction::set_compute_unit_price(priority_fee),
+            ComputeBudgetInstruction::request_heap_frame(128 * 1024),
+            send_funds_to_user_instruction(
+                &solver.pubkey(),
+                intent_id,
+                token_in_mint,
+                token_out_mint,
+                user,
+                solver_out,
+                single_domain,
+            )?,
+        ];
+
+        let recent_blockhash = rpc_client
+            .get_latest_blockhash()
+            .await
+            .map_err(|e| format!("Failed to get latest blockhash: {}", e))?;
+        let transaction = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&solver.pubkey()),
+            &[&solver],
+            recent_blockhash,
+        );
+
+        rpc_client
+            .send_and_confirm_transaction_with_spinner_and_config(
+                &transaction,
+                CommitmentConfig::processed(),
+                RpcSendTransactionConfig {
                     skip_preflight: true,
                     ..Default::default()
+                },
+            )
+            .await
+            .map_err(|e| format!("Transaction failed: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn fetch_address_lookup_tables(
+        rpc_client: &RpcClient,
+        addresses: &[Pubkey],
+    ) -> Result<Vec<AddressLookupTableAccount>, String> {
+        let accounts = rpc_client
+            .get_multiple_accounts(addresses)
+            .await
+            .map_err(|e| format!("Failed to fetch address lookup tables: {}", e))?;
+
+        addresses
+            .iter()
+            .zip(accounts)
+            .map(|(key, account)| {
+                let account =
+                    account.ok_or_else(|| format!("Address lookup table {key} not found"))?;
+                let table = AddressLookupTable::deserialize(&account.data)
+                    .map_err(|e| format!("Invalid address lookup table {key}: {e}"))?;
+                Ok(AddressLookupTableAccount {
+                    key: *key,
+                    addresses: table.addresses.to_vec(),
                 })
-                .map_err(|e| format!("Transaction failed: {}", e))
-                .map(|_| ()) // Map the Signature result to ()
-        })
+            })
+            .collect()
+    }
+
+    /// `solana_swap` then `solana_send_funds_to_user`, in two transactions.
+    #[allow(clippy::too_many_arguments)]
+    async fn solana_swap_then_send_funds_to_user(
+        intent_id: &str,
+        token_in_mint: &str,
+        token_out_mint: &str,
+        user: &str,
+        solver_out: String,
+        single_domain: bool,
+        swap_params: &SwapParams,
+        router: Option<&str>,
+        max_amount_in: &BigInt,
+        rpc_client: &RpcClient,
+        solver: &Keypair,
+    ) -> Result<(), String> {
+        solana_swap(swap_params, router, rpc_client, solver, Some(max_amount_in))
+            .await
+            .map_err(|e| format!("Swap failed: {}", e))?;
+        solana_send_funds_to_user(
+            intent_id,
+            token_in_mint,
+            token_out_mint,
+            user,
+            solver_out,
+            single_domain,
+        )
         .await
-        .map_err(|e| format!("Task failed: {:?}", e))?
+    }
+
+    /// Swaps USDT -> token_out through Jupiter and sends token_out to the user
+    /// in one versioned transaction, so a failed sendFundsToUser also reverts
+    /// the swap. Refuses a swap spending more than `max_amount_in` USDT. Falls
+    /// back to `solana_swap` + `solana_send_funds_to_user` when the router
+    /// isn't Jupiter or the combined transaction doesn't fit in a packet.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn solana_swap_and_send_funds_to_user(
+        intent_id: &str,
+        token_in_mint: &str,
+        token_out_mint: &str,
+        user: &str,
+        solver_out: String,
+        single_domain: bool,
+        swap_params: &SwapParams,
+        router: Option<&str>,
+        max_amount_in: &BigInt,
+    ) -> Result<(), String> {
+        let solana_keypair = env::var("SOLANA_KEYPAIR")
+            .map_err(|e| format!("Failed to read SOLANA_KEYPAIR from environment: {}", e))?;
+        let solver = Keypair::from_base58_string(&solana_keypair);
+
+        let rpc_url = env::var("SOLANA_RPC").expect("SOLANA_RPC must be set");
+        let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+
+        // only Jupiter hands out raw instructions
+        let router_name = select_router("solana", router)?.name();
+        if router_name != JupiterRouter.name() {
+            println!(
+                "{router_name} can't build an atomic swap for intent_id {intent_id}, sending swap and sendFundsToUser separately"
+            );
+            return solana_swap_then_send_funds_to_user(
+                intent_id,
+                token_in_mint,
+                token_out_mint,
+                user,
+                solver_out,
+                single_domain,
+                swap_params,
+                router,
+                max_amount_in,
+                &rpc_client,
+                &solver,
+            )
+            .await;
+        }
+
+        let token_in = Pubkey::from_str(token_in_mint)
+            .map_err(|e| format!("Invalid token_in_mint pubkey: {}", e))?;
+        let token_out = Pubkey::from_str(token_out_mint)
+            .map_err(|e| format!("Invalid token_out_mint pubkey: {}", e))?;
+        ensure_solver_token_account(&rpc_client, &solver, &token_in).await;
+
+        let quote = jupiter_quote(swap_params).await?;
+        if BigInt::from(quote.in_amount) > *max_amount_in {
+            return Err(format!(
+                "{} expects to spend {} token_in, above the {} budgeted",
+                router_name, quote.in_amount, max_amount_in
+            ));
+        }
+        let solver_token_out = get_associated_token_address(&solver.pubkey(), &token_out);
+        let swap_instructions = JUPITER_CLIENT
+            .swap_instructions(SwapRequest::new(solver.pubkey(), quote, solver_token_out))
+            .await
+            .map_err(|e| format!("Failed to get swap instructions: {}", e))?;
+        let priority_fee = fetch_solana_priority_fee()
+            .await
+            .map_err(|e| format!("Failed to fetch priority fee: {}", e))?;
+
+        // Jupiter's compute budget instructions are dropped, a transaction can
+        // only set its limit and price once and sendFundsToUser needs room on
+        // top of the swap. The price is the one the flat fee charged for.
+        let mut instructions = vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(ATOMIC_SWAP_COMPUTE_UNITS),
+            ComputeBudgetInstruction::set_compute_unit_price(priority_fee),
+            ComputeBudgetInstruction::request_heap_frame(128 * 1024),
+            create_associated_token_account_idempotent(
+                &solver.pubkey(),
+                &solver.pubkey(),
+                &token_out,
+                &spl_token::id(),
+            ),
+        ];
+        instructions.extend(swap_instructions.token_ledger_instruction);
+        instructions.extend(swap_instructions.setup_instructions);
+        instructions.push(swap_instructions.swap_instruction);
+        instructions.extend(swap_instructions.cleanup_instruction);
+        instructions.push(send_funds_to_user_instruction(
+            &solver.pubkey(),
+            intent_id,
+            token_in_mint,
+            token_out_mint,
+            user,
+            solver_out.clone(),
+            single_domain,
+        )?);
+
+        let lookup_tables = fetch_address_lookup_tables(
+            &rpc_client,
+            &swap_instructions.address_lookup_table_addresses,
+        )
+        .await?;
+
+        let recent_blockhash = rpc_client
+            .get_latest_blockhash()
+            .await
+            .map_err(|e| format!("Failed to get latest blockhash: {}", e))?;
+        let message = v0::Message::try_compile(
+            &solver.pubkey(),
+            &instructions,
+            &lookup_tables,
+            recent_blockhash,
+        )
+        .map_err(|e| format!("Failed to compile message: {}", e))?;
+        let transaction = VersionedTransaction::try_new(VersionedMessage::V0(message), &[&solver])
+            .map_err(|e| format!("Failed to create signed transaction: {}", e))?;
+
+        let size = bincode::serialized_size(&transaction)
+            .map_err(|e| format!("Failed to serialize transaction: {}", e))?;
+        if size > PACKET_DATA_SIZE as u64 {
+            println!(
+                "Atomic swap for intent_id {intent_id} is {size} bytes (max {PACKET_DATA_SIZE}), sending swap and sendFundsToUser separately"
+            );
+            return solana_swap_then_send_funds_to_user(
+                intent_id,
+                token_in_mint,
+                token_out_mint,
+                user,
+                solver_out,
+                single_domain,
+                swap_params,
+                router,
+                max_amount_in,
+                &rpc_client,
+                &solver,
+            )
+            .await;
+        }
+
+        // Simulate the transaction before sending
+        let simulation = rpc_client
+            .simulate_transaction(&transaction)
+            .await
+            .map_err(|e| format!("Transaction simulation failed: {}", e))?;
+        if let Some(err) = simulation.value.err {
+            return Err(format!("Transaction simulation failed: {}", err));
+        }
+
+        rpc_client
+            .send_and_confirm_transaction_with_spinner(&transaction)
+            .await
+            .map_err(|e| format!("Transaction failed: {}", e))?;
+
+        Ok(())
     }
 }
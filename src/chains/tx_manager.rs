@@ -0,0 +1,275 @@
+pub mod tx_manager {
+    use crate::chains::nonce_manager::nonce_manager::NONCE_MANAGER;
+    use crate::chains::revert::revert::{decode_revert, Revert};
+    use ethers::prelude::*;
+    use ethers::types::transaction::eip2718::TypedTransaction;
+    use lazy_static::lazy_static;
+    use std::env;
+
+    lazy_static! {
+        pub static ref TX_MANAGER_CONFIG: TxManagerConfig = TxManagerConfig::from_env();
+    }
+
+    /// Replacements must raise both fees by at least 10% to enter the mempool.
+    const MIN_BUMP_PERCENT: u64 = 10;
+
+    #[derive(thiserror::Error, Debug)]
+    pub enum TxError {
+        #[error("provider: {0}")]
+        Provider(String),
+
+        #[error("signer: {0}")]
+        Signer(String),
+
+        #[error("simulation reverted: {0}")]
+        Simulation(Revert),
+
+        #[error("transaction {0:?} reverted")]
+        Reverted(TxHash),
+
+        #[error("nonce {nonce} used by a transaction not sent by the solver (sent {hashes:?})")]
+        Replaced { nonce: U256, hashes: Vec<TxHash> },
+
+        #[error("nonce {nonce} not mined after {blocks} blocks and {attempts} attempts, fee cap {fee_cap} wei per gas reached: {hashes:?}")]
+        Stuck {
+            nonce: U256,
+            blocks: u64,
+            attempts: usize,
+            fee_cap: U256,
+            hashes: Vec<TxHash>,
+        },
+    }
+
+    /// When and how far a pending transaction is re-priced, from env:
+    /// `EVM_TX_BUMP_BLOCKS` (3), `EVM_TX_BUMP_PERCENT` (15),
+    /// `EVM_TX_FEE_CAP_MULTIPLIER` (3) and `EVM_TX_MAX_BLOCKS` (25).
+    #[derive(Debug, Clone)]
+    pub struct TxManagerConfig {
+        pub bump_after_blocks: u64,
+        pub bump_percent: u64,
+        // max_fee_per_gas never goes above the first estimate times this
+        pub fee_cap_multiplier: u64,
+        pub max_blocks: u64,
+    }
+
+    impl TxManagerConfig {
+        pub fn from_env() -> Self {
+            let var = |name: &str, default: u64| {
+                env::var(name)
+                    .ok()
+                    .and_then(|value| value.parse::<u64>().ok())
+                    .unwrap_or(default)
+            };
+
+            TxManagerConfig {
+                bump_after_blocks: var("EVM_TX_BUMP_BLOCKS", 3).max(1),
+                bump_percent: var("EVM_TX_BUMP_PERCENT", 15).max(MIN_BUMP_PERCENT),
+                fee_cap_multiplier: var("EVM_TX_FEE_CAP_MULTIPLIER", 3).max(1),
+                max_blocks: var("EVM_TX_MAX_BLOCKS", 25),
+            }
+        }
+    }
+
+    fn provider_error(e: impl std::fmt::Display) -> TxError {
+        TxError::Provider(e.to_string())
+    }
+
+    fn bump(fee: U256, percent: u64) -> U256 {
+        fee * U256::from(100 + percent) / U256::from(100)
+    }
+
+    pub fn to_eip1559(tx: TypedTransaction) -> Eip1559TransactionRequest {
+        match tx {
+            TypedTransaction::Eip1559(tx) => tx,
+            tx => Eip1559TransactionRequest {
+                from: tx.from().copied(),
+                to: tx.to().cloned(),
+                gas: tx.gas().copied(),
+                value: tx.value().copied(),
+                data: tx.data().cloned(),
+                nonce: tx.nonce().copied(),
+                chain_id: tx.chain_id(),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// `eth_call` of `tx` at the pending block, so a transaction that would
+    /// revert is never broadcast and its revert reason is kept.
+    pub async fn simulate<M: Middleware>(
+        client: &SignerMiddleware<M, LocalWallet>,
+        tx: &TypedTransaction,
+    ) -> Result<(), TxError> {
+        let e = match client.call(tx, Some(BlockNumber::Pending.into())).await {
+            Ok(_) => return Ok(()),
+            Err(e) => e,
+        };
+        let revert_data = e.as_error_response().and_then(|e| e.as_revert_data());
+        match revert_data {
+            Some(data) => Err(TxError::Simulation(decode_revert(&data))),
+            None => Err(provider_error(e)),
+        }
+    }
+
+    async fn send_signed<M: Middleware>(
+        client: &SignerMiddleware<M, LocalWallet>,
+        tx: &Eip1559TransactionRequest,
+    ) -> Result<TxHash, TxError> {
+        let typed = TypedTransaction::Eip1559(tx.clone());
+        let signature = client
+            .signer()
+            .sign_transaction(&typed)
+            .await
+            .map_err(|e| TxError::Signer(e.to_string()))?;
+
+        let pending_tx = client
+            .send_raw_transaction(typed.rlp_signed(&signature))
+            .await
+            .map_err(provider_error)?;
+        Ok(pending_tx.tx_hash())
+    }
+
+    pub async fn find_receipt<M: Middleware>(
+        client: &SignerMiddleware<M, LocalWallet>,
+        hashes: &[TxHash],
+    ) -> Result<Option<TransactionReceipt>, TxError> {
+        for hash in hashes {
+            if let Some(receipt) = client
+                .get_transaction_receipt(*hash)
+                .await
+                .map_err(provider_error)?
+            {
+                return Ok(Some(receipt));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Simulates `tx`, then sends it as an EIP-1559 transaction with a nonce
+    /// from `NONCE_MANAGER` and waits for it. Every `bump_after_blocks` blocks without a receipt it
+    /// is replaced, same nonce, with both fees raised by `bump_percent`, until
+    /// max_fee_per_gas would pass the cap. Gives up after `max_blocks`.
+    pub async fn send_and_confirm<M: Middleware>(
+        client: &SignerMiddleware<M, LocalWallet>,
+        tx: TypedTransaction,
+        config: &TxManagerConfig,
+    ) -> Result<TransactionReceipt, TxError> {
+        let chain_id = client.signer().chain_id();
+        let account = client.address();
+        let mut tx = to_eip1559(tx);
+        tx.from = Some(account);
+        tx.chain_id = Some(chain_id.into());
+
+        if tx.max_fee_per_gas.is_none() || tx.max_priority_fee_per_gas.is_none() {
+            let (max_fee_per_gas, max_priority_fee_per_gas) =
+                client.estimate_eip1559_fees(None).await.map_err(provider_error)?;
+            tx.max_fee_per_gas = Some(max_fee_per_gas);
+            tx.max_priority_fee_per_gas = Some(max_priority_fee_per_gas);
+        }
+        let fee_cap =
+            tx.max_fee_per_gas.unwrap_or_default() * U256::from(config.fee_cap_multiplier);
+
+        simulate(client, &TypedTransaction::Eip1559(tx.clone())).await?;
+
+        if tx.gas.is_none() {
+            let gas = client
+                .estimate_gas(&TypedTransaction::Eip1559(tx.clone()), None)
+                .await
+                .map_err(provider_error)?;
+            tx.gas = Some(gas);
+        }
+
+        let nonce = NONCE_MANAGER
+            .reserve(client, chain_id, account)
+            .await
+            .map_err(TxError::Provider)?;
+        tx.nonce = Some(nonce);
+
+        let mut hashes = match send_signed(client, &tx).await {
+            Ok(hash) => {
+                NONCE_MANAGER.sent(chain_id, account, nonce).await;
+                vec![hash]
+            }
+            Err(e) => {
+                NONCE_MANAGER.release(chain_id, account, nonce).await;
+                if e.to_string().to_lowercase().contains("nonce") {
+                    // e.g. "nonce too low" after a send from outside the solver
+                    NONCE_MANAGER.resync(chain_id, account).await;
+                }
+                return Err(e);
+            }
+        };
+
+        let first_block = client.get_block_number().await.map_err(provider_error)?;
+        let mut priced_at = first_block;
+
+        loop {
+            tokio::time::sleep(client.provider().get_interval()).await;
+
+            // any of the sent versions may be the one that got mined
+            if let Some(receipt) = find_receipt(client, &hashes).await? {
+                return if receipt.status == Some(U64::from(1)) {
+                    Ok(receipt)
+                } else {
+                    Err(TxError::Reverted(receipt.transaction_hash))
+                };
+            }
+
+            let mined_nonce = client
+                .get_transaction_count(account, Some(BlockNumber::Latest.into()))
+                .await
+                .map_err(provider_error)?;
+            if mined_nonce > nonce {
+                // a receipt may show up a moment after the nonce moves
+                return match find_receipt(client, &hashes).await? {
+                    Some(receipt) if receipt.status == Some(U64::from(1)) => Ok(receipt),
+                    Some(receipt) => Err(TxError::Reverted(receipt.transaction_hash)),
+                    None => {
+                        NONCE_MANAGER.resync(chain_id, account).await;
+                        Err(TxError::Replaced { nonce, hashes })
+                    }
+                };
+            }
+
+            let block = client.get_block_number().await.map_err(provider_error)?;
+            let blocks = (block - first_block).as_u64();
+            if blocks >= config.max_blocks {
+                return Err(TxError::Stuck {
+                    nonce,
+                    blocks,
+                    attempts: hashes.len(),
+                    fee_cap,
+                    hashes,
+                });
+            }
+            if (block - priced_at).as_u64() < config.bump_after_blocks {
+                continue;
+            }
+
+            let max_fee_per_gas =
+                bump(tx.max_fee_per_gas.unwrap_or_default(), config.bump_percent);
+            if max_fee_per_gas > fee_cap {
+                // keep waiting on the last price until max_blocks
+                continue;
+            }
+            tx.max_fee_per_gas = Some(max_fee_per_gas);
+            tx.max_priority_fee_per_gas = Some(bump(
+                tx.max_priority_fee_per_gas.unwrap_or_default(),
+                config.bump_percent,
+            ));
+            priced_at = block;
+
+            match send_signed(client, &tx).await {
+                Ok(hash) => {
+                    println!(
+                        "Transaction nonce {nonce} pending for {blocks} blocks, replaced with {:?} at max_fee_per_gas {max_fee_per_gas}",
+                        hash
+                    );
+                    hashes.push(hash);
+                }
+                // e.g. "nonce too low" when the previous version was just mined
+                Err(e) => println!("Failed to replace transaction nonce {nonce}: {e}"),
+            }
+        }
+    }
+}
@@ -1,16 +1,30 @@
 mod chains;
 mod routers;
+mod strategies;
 
-use crate::chains::ethereum::ethereum_chain::handle_ethereum_execution;
+use crate::chains::escrow_listener::escrow_listener::{
+    ensure_escrowed, spawn_escrow_listeners, wait_for_escrow_event,
+};
+use crate::chains::ethereum::ethereum_chain::{
+    evm_network, handle_evm_execution, verify_escrow_intent,
+};
+use crate::chains::executor::solver_executor::deploy_solver_executor;
 use crate::chains::solana::solana_chain::handle_solana_execution;
 use crate::chains::OperationInput;
 use crate::chains::OperationOutput;
+use crate::chains::registered_solver_addresses;
 use crate::chains::PostIntentInfo;
 use crate::chains::INTENTS;
-use crate::chains::SOLVER_ADDRESSES;
 use crate::chains::SOLVER_ID;
 use crate::chains::SOLVER_PRIVATE_KEY;
+use crate::routers::check_quote_drift;
 use crate::routers::get_simulate_swap_intent;
+use crate::routers::QuoteError;
+use crate::routers::QuoteResult;
+use crate::routers::QUOTES;
+use crate::strategies::BidDecision;
+use crate::strategies::Inventory;
+use crate::strategies::BID_STRATEGY;
 use chains::create_keccak256_signature;
 use ethers::types::U256;
 use futures::{SinkExt, StreamExt};
@@ -26,8 +40,32 @@ async fn main() {
 
     dotenv::dotenv().ok();
 
-    //Start Fetching flat fee
-    // start_fee_updater().await;
+    // cargo run -- deploy-executor <network> [path to SolverExecutor.bin]
+    let args: Vec<String> = env::args().collect();
+    if args.get(1).map(String::as_str) == Some("deploy-executor") {
+        let network = args
+            .get(2)
+            .and_then(|name| evm_network(name))
+            .expect("usage: deploy-executor <network> [bytecode path]");
+        let bytecode_path = args
+            .get(3)
+            .cloned()
+            .unwrap_or_else(|| "contracts/out/SolverExecutor.bin".to_string());
+        let bytecode = std::fs::read_to_string(&bytecode_path)
+            .unwrap_or_else(|e| panic!("Failed to read {bytecode_path}: {e}"));
+
+        match deploy_solver_executor(network, &bytecode).await {
+            Ok(executor) => println!(
+                "SolverExecutor deployed on {} at {:?}, set SOLVER_EXECUTOR={:?} once it is deployed on every EVM network you solve on",
+                network.name, executor, executor
+            ),
+            Err(e) => println!("Failed to deploy SolverExecutor on {}: {e}", network.name),
+        }
+        return;
+    }
+
+    // follow escrow events on every EVM network with an escrow
+    spawn_escrow_listeners();
 
     let server_addr = env::var("COMPOSABLE_ENDPOINT").expect("COMPOSABLE_ENDPOINT must be set in .env file");
     let (ws_stream, _) = connect_async(server_addr).await.expect("Failed to connect");
@@ -37,7 +75,7 @@ async fn main() {
         "code": 1,
         "msg": {
             "solver_id": SOLVER_ID.to_string(),
-            "solver_addresses": SOLVER_ADDRESSES,
+            "solver_addresses": registered_solver_addresses(),
         }
     });
 
@@ -83,13 +121,27 @@ async fn main() {
                     let intent_info: PostIntentInfo = serde_json::from_value(intent_value).unwrap();
 
                     // calculate best quote
-                    let final_amount = get_simulate_swap_intent(
+                    let bridge_token = String::from("USDT");
+                    let mut quote = match get_simulate_swap_intent(
                         &intent_info,
                         &intent_info.src_chain,
                         &intent_info.dst_chain,
-                        &String::from("USDT"),
+                        &bridge_token,
                     )
-                    .await;
+                    .await
+                    {
+                        Ok(quote) => quote,
+                        Err(e @ QuoteError::BelowFlatFee { .. }) => {
+                            println!("Not participating in intent {intent_id}: {e}");
+                            continue;
+                        }
+                        Err(e) => {
+                            println!("Failed to quote intent {intent_id}: {e}");
+                            continue;
+                        }
+                    };
+
+                    println!("Quote for intent {intent_id}: {:#?}", quote);
 
                     // decide if participate or not
                     let mut amount_out_min = U256::zero();
@@ -97,32 +149,44 @@ async fn main() {
                         amount_out_min = U256::from_dec_str(&transfer_output.amount_out).unwrap();
                     }
 
-                    let final_amount = U256::from_dec_str(&final_amount).unwrap();
+                    let inventory = Inventory::fetch(&bridge_token).await;
 
-                    println!("User wants {amount_out_min} token_out, you can provide {final_amount} token_out (after FLAT_FEES + COMISSION)");
+                    match BID_STRATEGY.bid(&intent_info, &quote, &inventory) {
+                        BidDecision::Bid { amount_out, commission } => {
+                            let final_amount = U256::from_dec_str(&amount_out.to_string()).unwrap();
 
-                    if final_amount > amount_out_min {
-                        let mut json_data = json!({
-                            "code": 2,
-                            "msg": {
-                                "intent_id": intent_id,
-                                "solver_id": SOLVER_ID.to_string(),
-                                "amount": final_amount.to_string()
-                            }
-                        });
+                            println!("User wants {amount_out_min} token_out, you can provide {final_amount} token_out (after FLAT_FEES + {} margin)", BID_STRATEGY.name());
 
-                        create_keccak256_signature(&mut json_data, SOLVER_PRIVATE_KEY.to_string())
-                            .await
-                            .unwrap();
+                            let mut json_data = json!({
+                                "code": 2,
+                                "msg": {
+                                    "intent_id": intent_id,
+                                    "solver_id": SOLVER_ID.to_string(),
+                                    "amount": final_amount.to_string()
+                                }
+                            });
 
-                        ws_sender
-                            .send(Message::text(json_data.to_string()))
-                            .await
-                            .expect("Failed to send message");
+                            create_keccak256_signature(&mut json_data, SOLVER_PRIVATE_KEY.to_string())
+                                .await
+                                .unwrap();
 
-                        let mut intents = INTENTS.write().await;
-                        intents.insert(intent_id.to_string(), intent_info);
-                        drop(intents);
+                            ws_sender
+                                .send(Message::text(json_data.to_string()))
+                                .await
+                                .expect("Failed to send message");
+
+                            let mut intents = INTENTS.write().await;
+                            intents.insert(intent_id.to_string(), intent_info);
+                            drop(intents);
+
+                            quote.commission = commission;
+                            let mut quotes = QUOTES.write().await;
+                            quotes.insert(intent_id.to_string(), quote);
+                            drop(quotes);
+                        }
+                        BidDecision::Decline(reason) => {
+                            println!("Not participating in intent {intent_id}: {reason}");
+                        }
                     }
                 } else if code == 3 {
                     // solver registered
@@ -149,21 +213,25 @@ async fn main() {
                         .to_string();
 
                     if msg.contains("won") {
-                        let intent;
-                        {
-                            let intents = INTENTS.read().await;
-                            intent = intents.get(intent_id).unwrap().clone();
-                            drop(intents);
-                        }
+                        let stored = {
+                            let intent = INTENTS.read().await.get(intent_id).cloned();
+                            let quote = QUOTES.read().await.get(intent_id).cloned();
+                            intent.zip(quote)
+                        };
 
-                        if intent.dst_chain == "solana" {
-                            handle_solana_execution(&intent, intent_id, amount)
-                                .await
-                                .unwrap();
-                        } else if intent.dst_chain == "ethereum" {
-                            handle_ethereum_execution(&intent, intent_id, amount)
-                                .await
-                                .unwrap();
+                        match stored {
+                            None => println!("Won intent {intent_id} has no stored intent or quote, not executing"),
+                            // waiting for the escrow and filling take blocks, the
+                            // auctioneer's next messages are handled meanwhile
+                            Some((intent, quote)) => {
+                                let intent_id = intent_id.to_string();
+                                let amount = amount.to_string();
+                                tokio::spawn(async move {
+                                    execute_intent(&intent, &intent_id, &amount, &quote).await;
+                                    // the fill moved USDT between chains
+                                    Inventory::invalidate().await;
+                                });
+                            }
                         }
 
                         // ws_sender.send(Message::text(msg)).await.expect("Failed to send message");
@@ -173,6 +241,10 @@ async fn main() {
                         let mut intents = INTENTS.write().await;
                         intents.remove(&intent_id.to_string());
                         drop(intents);
+
+                        let mut quotes = QUOTES.write().await;
+                        quotes.remove(&intent_id.to_string());
+                        drop(quotes);
                     }
                     }
                 }
@@ -185,3 +257,34 @@ async fn main() {
     println!("Auctioner went down, please reconnect");
 }
 
+/// Checks a won intent against its quote and the escrow, then fills it.
+async fn execute_intent(intent: &PostIntentInfo, intent_id: &str, amount: &str, quote: &QuoteResult) {
+    if let Err(e) = check_quote_drift(intent, amount, quote).await {
+        println!("Not executing intent {intent_id}: {e}");
+    } else if let Err(e) = ensure_escrowed(intent, intent_id).await {
+        println!("Not executing intent {intent_id}: {e}");
+    } else if let Err(e) = verify_escrow_intent(intent, intent_id, amount).await {
+        println!("Not executing intent {intent_id}: {e}");
+    } else if intent.dst_chain == "solana" {
+        if let Err(e) = handle_solana_execution(intent, intent_id, amount, quote).await {
+            println!("Failed to execute intent {intent_id} on solana: {e}");
+        }
+    } else if let Some(network) = evm_network(&intent.dst_chain) {
+        if let Err(e) = handle_evm_execution(network, intent, intent_id, amount, quote).await {
+            println!("Failed to execute intent {intent_id} on {}: {e}", network.name);
+            return;
+        }
+
+        // the escrow's FundsSentToUser event confirms the settlement
+        let settled = wait_for_escrow_event(
+            network,
+            intent_id,
+            std::time::Duration::from_secs(300),
+            |intent| intent.sent_by_solver(),
+        )
+        .await;
+        if settled.is_none() {
+            println!("No FundsSentToUser event for intent {intent_id} on {} after 300s, check the settlement", network.name);
+        }
+    }
+}
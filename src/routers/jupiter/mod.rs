@@ -4,7 +4,7 @@ pub mod field_prioritization_fee;
 pub mod field_pubkey;
 
 use solana_sdk::transaction::VersionedTransaction;
-use std::{env, fmt, str::FromStr};
+use std::{env, fmt, str::FromStr, time::Duration};
 
 use {
     serde::{Deserialize, Serialize},
@@ -20,7 +20,13 @@ use {
 };
 
 use crate::get_associated_token_address;
-use serde_json::Value;
+use crate::routers::swap_router::{SwapParams, SwapQuote, SwapRouter, SwapSide, SwapTransaction};
+use futures::future::BoxFuture;
+use lazy_static::lazy_static;
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
 use solana_sdk::pubkey;
 use solana_sdk::signer::keypair::Keypair;
 use spl_associated_token_account::instruction;
@@ -29,13 +35,15 @@ use spl_associated_token_account::instruction;
 pub type Result<T> = std::result::Result<T, Error>;
 
 // Reference: https://quote-api.jup.ag/v4/docs/static/index.html
-fn quote_api_url() -> String {
-    env::var("QUOTE_API_URL").unwrap_or_else(|_| "https://quote-api.jup.ag/v6".to_string())
-}
-
+const DEFAULT_QUOTE_API_URL: &str = "https://quote-api.jup.ag/v6";
 // Reference: https://quote-api.jup.ag/docs/static/index.html
-fn _price_api_url() -> String {
-    env::var("PRICE_API_URL").unwrap_or_else(|_| "https://price.jup.ag/v1".to_string())
+const DEFAULT_PRICE_API_URL: &str = "https://price.jup.ag/v1";
+const DEFAULT_JUPITER_TIMEOUT: u64 = 10_000; // in milliseconds
+const DEFAULT_JUPITER_MAX_RETRIES: u32 = 2;
+const JUPITER_RETRY_BACKOFF: u64 = 200; // in milliseconds, grows linearly per attempt
+
+lazy_static! {
+    pub static ref JUPITER_CLIENT: JupiterClient = JupiterClient::from_env();
 }
 
 /// The Errors that may occur while using this crate
@@ -50,6 +58,9 @@ pub enum Error {
     #[error("bincode: {0}")]
     Bincode(#[from] bincode::Error),
 
+    #[error("base64: {0}")]
+    Base64(#[from] base64::DecodeError),
+
     #[error("Jupiter API: {0}")]
     JupiterApi(String),
 
@@ -159,22 +170,17 @@ pub struct Swap {
 #[derive(Clone, Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SwapInstructions {
-    #[allow(dead_code)]
     #[serde(with = "field_instruction::option_instruction")]
     pub token_ledger_instruction: Option<Instruction>,
     #[allow(dead_code)]
     #[serde(with = "field_instruction::vec_instruction")]
     pub compute_budget_instructions: Vec<Instruction>,
-    #[allow(dead_code)]
     #[serde(with = "field_instruction::vec_instruction")]
     pub setup_instructions: Vec<Instruction>,
-    #[allow(dead_code)]
     #[serde(with = "field_instruction::instruction")]
     pub swap_instruction: Instruction,
-    #[allow(dead_code)]
     #[serde(with = "field_instruction::option_instruction")]
     pub cleanup_instruction: Option<Instruction>,
-    #[allow(dead_code)]
     #[serde(with = "field_pubkey::vec")]
     pub address_lookup_table_addresses: Vec<Pubkey>,
     #[allow(dead_code)]
@@ -200,13 +206,11 @@ where
     }
 }
 
-/// Get simple price for a given input mint, output mint, and amount
-pub async fn _price(input_mint: Pubkey, output_mint: Pubkey, ui_amount: f64) -> Result<Price> {
-    let url = format!(
-        "{base_url}/price?id={input_mint}&vsToken={output_mint}&amount={ui_amount}",
-        base_url = _price_api_url(),
-    );
-    maybe_jupiter_api_error(reqwest::get(url).await?.json().await?)
+/// Base64 bincode transaction of a `/swap` response.
+fn decode_transaction(base64_transaction: String) -> Result<VersionedTransaction> {
+    #[allow(deprecated)]
+    let bytes = base64::decode(base64_transaction)?;
+    Ok(bincode::deserialize(&bytes)?)
 }
 
 #[derive(Serialize, Deserialize, Default, PartialEq, Clone, Debug)]
@@ -237,60 +241,52 @@ impl fmt::Display for SwapMode {
     }
 }
 
-#[derive(Default)]
+/// Optional parameters of `GET /quote`, URL-encoded as its query string.
+/// Unset parameters are left to Jupiter's defaults.
+#[derive(Serialize, Default, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct QuoteConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub slippage_bps: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_slippage: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_auto_slippage_bps: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_slippage_collision_usd_value: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compute_auto_slippage: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub swap_mode: Option<SwapMode>,
-    pub dexes: Option<Vec<Pubkey>>,
-    pub exclude_dexes: Option<Vec<Pubkey>>,
-    pub only_direct_routes: bool,
+    // DEX labels, e.g. "Raydium,Orca V2"
+    #[serde(serialize_with = "comma_separated", skip_serializing_if = "Option::is_none")]
+    pub dexes: Option<Vec<String>>,
+    #[serde(serialize_with = "comma_separated", skip_serializing_if = "Option::is_none")]
+    pub exclude_dexes: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restrict_intermediate_tokens: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub only_direct_routes: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub as_legacy_transaction: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub platform_fee_bps: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub max_accounts: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minimize_slippage: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prefer_liquid_dexes: Option<bool>,
 }
 
-/// Get quote for a given input mint, output mint, and amount
-pub async fn quote(
-    input_mint: Pubkey,
-    output_mint: Pubkey,
-    amount: u64,
-    quote_config: QuoteConfig,
-) -> Result<Quote> {
-    let url = format!(
-        "{base_url}/quote?inputMint={input_mint}&outputMint={output_mint}&amount={amount}&onlyDirectRoutes={}&{}{}{}{}{}{}{}",
-        quote_config.only_direct_routes,
-        quote_config
-            .as_legacy_transaction
-            .map(|as_legacy_transaction| format!("&asLegacyTransaction={as_legacy_transaction}"))
-            .unwrap_or_default(),
-        quote_config
-            .swap_mode
-            .map(|swap_mode| format!("&swapMode={swap_mode}"))
-            .unwrap_or_default(),
-        quote_config
-            .slippage_bps
-            .map(|slippage_bps| format!("&slippageBps={slippage_bps}"))
-            .unwrap_or_default(),
-        quote_config
-            .platform_fee_bps
-            .map(|platform_fee_bps| format!("&feeBps={platform_fee_bps}"))
-            .unwrap_or_default(),
-        quote_config
-            .dexes
-            .map(|dexes| format!("&dexes={:?}", dexes))
-            .unwrap_or_default(),
-        quote_config
-            .exclude_dexes
-            .map(|exclude_dexes| format!("&excludeDexes={:?}", exclude_dexes))
-            .unwrap_or_default(),
-        quote_config
-            .max_accounts
-            .map(|max_accounts| format!("&maxAccounts={max_accounts}"))
-            .unwrap_or_default(),
-        base_url=quote_api_url(),
-    );
-
-    maybe_jupiter_api_error(reqwest::get(url).await?.json().await?)
+fn comma_separated<S>(labels: &Option<Vec<String>>, serializer: S) -> std::result::Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match labels {
+        Some(labels) => serializer.serialize_str(&labels.join(",")),
+        None => serializer.serialize_none(),
+    }
 }
 
 #[derive(Debug)]
@@ -353,185 +349,315 @@ struct SwapResponse {
     pub last_valid_block_height: u64,
 }
 
-/// Get swap serialized transactions for a quote
-pub async fn swap(swap_request: SwapRequest) -> Result<Swap> {
-    let url = format!("{}/swap", quote_api_url());
-
-    let response = maybe_jupiter_api_error::<SwapResponse>(
-        reqwest::Client::builder()
-            .build()?
-            .post(url)
-            .header("Accept", "application/json")
-            .json(&swap_request)
-            .send()
-            .await?
-            .error_for_status()?
-            .json()
-            .await?,
-    )?;
+/// Jupiter HTTP client sharing one connection pool, with an optional API key,
+/// a per-request timeout and retries on timeouts, 429 and 5xx responses.
+#[derive(Clone, Debug)]
+pub struct JupiterClient {
+    http: reqwest::Client,
+    quote_api_url: String,
+    price_api_url: String,
+    api_key: Option<String>,
+    timeout: Duration,
+    max_retries: u32,
+    retry_backoff: Duration,
+}
 
-    fn decode(base64_transaction: String) -> Result<VersionedTransaction> {
-        #[allow(deprecated)]
-        bincode::deserialize(&base64::decode(base64_transaction).unwrap()).map_err(|err| err.into())
+impl JupiterClient {
+    pub fn new(quote_api_url: impl Into<String>, price_api_url: impl Into<String>) -> Self {
+        JupiterClient {
+            http: reqwest::Client::new(),
+            quote_api_url: quote_api_url.into(),
+            price_api_url: price_api_url.into(),
+            api_key: None,
+            timeout: Duration::from_millis(DEFAULT_JUPITER_TIMEOUT),
+            max_retries: DEFAULT_JUPITER_MAX_RETRIES,
+            retry_backoff: Duration::from_millis(JUPITER_RETRY_BACKOFF),
+        }
     }
 
-    Ok(Swap {
-        swap_transaction: decode(response.swap_transaction)?,
-        last_valid_block_height: response.last_valid_block_height,
-    })
-}
-
-/// Get swap serialized transaction instructions for a quote
-pub async fn _swap_instructions(swap_request: SwapRequest) -> Result<SwapInstructions> {
-    let url = format!("{}/swap-instructions", quote_api_url());
+    pub fn from_env() -> Self {
+        let client = JupiterClient::new(
+            env::var("QUOTE_API_URL").unwrap_or_else(|_| DEFAULT_QUOTE_API_URL.to_string()),
+            env::var("PRICE_API_URL").unwrap_or_else(|_| DEFAULT_PRICE_API_URL.to_string()),
+        )
+        .with_timeout(Duration::from_millis(
+            env::var("JUPITER_TIMEOUT")
+                .ok()
+                .and_then(|timeout| timeout.parse::<u64>().ok())
+                .unwrap_or(DEFAULT_JUPITER_TIMEOUT),
+        ))
+        .with_retries(
+            env::var("JUPITER_MAX_RETRIES")
+                .ok()
+                .and_then(|retries| retries.parse::<u32>().ok())
+                .unwrap_or(DEFAULT_JUPITER_MAX_RETRIES),
+            Duration::from_millis(JUPITER_RETRY_BACKOFF),
+        );
 
-    let response = reqwest::Client::builder()
-        .build()?
-        .post(url)
-        .header("Accept", "application/json")
-        .json(&swap_request)
-        .send()
-        .await?;
+        match env::var("JUPITER_API_KEY") {
+            Ok(api_key) if !api_key.is_empty() => client.with_api_key(api_key),
+            _ => client,
+        }
+    }
 
-    if !response.status().is_success() {
-        return Err(Error::JupiterApi(response.text().await?));
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
     }
 
-    Ok(response.json::<SwapInstructions>().await?)
-}
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
 
-/// Returns a hash map, input mint as key and an array of valid output mint as values
-pub async fn _route_map() -> Result<RouteMap> {
-    let url = format!(
-        "{}/indexed-route-map?onlyDirectRoutes=false",
-        quote_api_url()
-    );
+    pub fn with_retries(mut self, max_retries: u32, retry_backoff: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.retry_backoff = retry_backoff;
+        self
+    }
 
-    #[derive(Debug, Deserialize)]
-    #[serde(rename_all = "camelCase")]
-    struct IndexedRouteMap {
-        _mint_keys: Vec<String>,
-        _indexed_route_map: HashMap<usize, Vec<usize>>,
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let mut attempt = 0;
+        loop {
+            let mut attempt_request = request
+                .try_clone()
+                .expect("Jupiter requests don't stream their body")
+                .timeout(self.timeout);
+            if let Some(api_key) = &self.api_key {
+                attempt_request = attempt_request.header("x-api-key", api_key);
+            }
+
+            match attempt_request.send().await {
+                Ok(response)
+                    if attempt < self.max_retries
+                        && (response.status().is_server_error()
+                            || response.status() == StatusCode::TOO_MANY_REQUESTS) => {}
+                Ok(response) => return Ok(response),
+                Err(err) if attempt < self.max_retries && (err.is_timeout() || err.is_connect()) => {}
+                Err(err) => return Err(err.into()),
+            }
+
+            attempt += 1;
+            tokio::time::sleep(self.retry_backoff * attempt).await;
+        }
     }
 
-    let response = reqwest::get(url).await?.json::<IndexedRouteMap>().await?;
+    async fn get<T: DeserializeOwned>(&self, request: reqwest::RequestBuilder) -> Result<T> {
+        maybe_jupiter_api_error(self.send(request).await?.json().await?)
+    }
 
-    let mint_keys = response
-        ._mint_keys
-        .into_iter()
-        .map(|x| x.parse::<Pubkey>().map_err(|err| err.into()))
-        .collect::<Result<Vec<Pubkey>>>()?;
+    /// Get simple price for a given input mint, output mint, and amount
+    pub async fn _price(
+        &self,
+        input_mint: Pubkey,
+        output_mint: Pubkey,
+        ui_amount: f64,
+    ) -> Result<Price> {
+        let request = self
+            .http
+            .get(format!("{}/price", self.price_api_url))
+            .query(&[
+                ("id", input_mint.to_string()),
+                ("vsToken", output_mint.to_string()),
+                ("amount", ui_amount.to_string()),
+            ]);
+        self.get(request).await
+    }
 
-    let mut route_map = HashMap::new();
-    for (from_index, to_indices) in response._indexed_route_map {
-        route_map.insert(
-            mint_keys[from_index],
-            to_indices.into_iter().map(|i| mint_keys[i]).collect(),
-        );
+    /// Get quote for a given input mint, output mint, and amount
+    pub async fn quote(
+        &self,
+        input_mint: Pubkey,
+        output_mint: Pubkey,
+        amount: u64,
+        quote_config: QuoteConfig,
+    ) -> Result<Quote> {
+        let request = self
+            .http
+            .get(format!("{}/quote", self.quote_api_url))
+            .query(&[
+                ("inputMint", input_mint.to_string()),
+                ("outputMint", output_mint.to_string()),
+                ("amount", amount.to_string()),
+            ])
+            .query(&quote_config);
+
+        self.get(request).await
     }
 
-    Ok(route_map)
-}
+    /// Get swap serialized transactions for a quote
+    pub async fn swap(&self, swap_request: SwapRequest) -> Result<Swap> {
+        let url = format!("{}/swap", self.quote_api_url);
+
+        let response = maybe_jupiter_api_error::<SwapResponse>(
+            self.send(
+                self.http
+                    .post(url)
+                    .header("Accept", "application/json")
+                    .json(&swap_request),
+            )
+            .await?
+            .error_for_status()?
+            .json()
+            .await?,
+        )?;
 
-#[derive(PartialEq, Debug, Default, Serialize, Deserialize)]
-pub struct Memo {
-    pub user_account: pubkey::Pubkey,
-    pub token_in: pubkey::Pubkey,
-    pub token_out: pubkey::Pubkey,
-    pub amount: u64,
-    pub slippage_bps: u64,
-}
+        Ok(Swap {
+            swap_transaction: decode_transaction(response.swap_transaction)?,
+            last_valid_block_height: response.last_valid_block_height,
+        })
+    }
 
-impl Memo {
-    pub fn from_json(json_str: &str) -> Result<Memo> {
-        let parsed_json: Value = serde_json::from_str(json_str).unwrap();
-        let mut memo = Memo::default();
-
-        memo.user_account =
-            Pubkey::from_str(&parsed_json["user_account"].to_string().trim_matches('"')).unwrap();
-        memo.token_in =
-            Pubkey::from_str(&parsed_json["token_in"].to_string().trim_matches('"')).unwrap();
-        memo.token_out =
-            Pubkey::from_str(&parsed_json["token_out"].to_string().trim_matches('"')).unwrap();
-        memo.amount = parsed_json["amount"].as_u64().unwrap_or_default();
-        memo.slippage_bps = parsed_json["slippage_bps"].as_u64().unwrap_or_default();
-
-        Ok(memo)
+    /// Get swap serialized transaction instructions for a quote
+    pub async fn swap_instructions(&self, swap_request: SwapRequest) -> Result<SwapInstructions> {
+        let url = format!("{}/swap-instructions", self.quote_api_url);
+
+        let response = self
+            .send(
+                self.http
+                    .post(url)
+                    .header("Accept", "application/json")
+                    .json(&swap_request),
+            )
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(Error::JupiterApi(response.text().await?));
+        }
+
+        Ok(response.json::<SwapInstructions>().await?)
     }
-}
 
-pub async fn jupiter_swap(
-    _memo: &str,
-    rpc_client: &RpcClient,
-    keypair: &Keypair,
-    swap_mode: SwapMode,
-) -> core::result::Result<(), String> {
-    // Parse the memo JSON
-    let memo = Memo::from_json(&_memo).map_err(|e| format!("Failed to parse memo: {}", e))?;
-
-    let only_direct_routes = false;
-    let quotes = quote(
-        memo.token_in,
-        memo.token_out,
-        memo.amount,
-        QuoteConfig {
-            only_direct_routes,
-            swap_mode: Some(swap_mode),
-            slippage_bps: Some(memo.slippage_bps),
-            ..QuoteConfig::default()
-        },
-    )
-    .await
-    .map_err(|e| format!("Failed to get quotes: {}", e))?;
+    /// Returns a hash map, input mint as key and an array of valid output mint as values
+    pub async fn _route_map(&self) -> Result<RouteMap> {
+        let url = format!(
+            "{}/indexed-route-map?onlyDirectRoutes=false",
+            self.quote_api_url
+        );
 
-    let user_token_out = get_associated_token_address(&memo.user_account, &memo.token_out);
+        #[derive(Debug, Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct IndexedRouteMap {
+            _mint_keys: Vec<String>,
+            _indexed_route_map: HashMap<usize, Vec<usize>>,
+        }
 
-    // Check if the user token account exists, and create it if necessary
-    if rpc_client
-        .get_token_account_balance(&user_token_out)
-        .await
-        .is_err()
-    {
-        create_token_account(&memo.user_account, &memo.token_out, &keypair, &rpc_client)
-            .await
-            .map_err(|e| format!("Failed to create token account: {}", e))?;
-    }
+        let response = self
+            .send(self.http.get(url))
+            .await?
+            .json::<IndexedRouteMap>()
+            .await?;
+
+        let mint_keys = response
+            ._mint_keys
+            .into_iter()
+            .map(|x| x.parse::<Pubkey>().map_err(|err| err.into()))
+            .collect::<Result<Vec<Pubkey>>>()?;
+
+        let mut route_map = HashMap::new();
+        for (from_index, to_indices) in response._indexed_route_map {
+            route_map.insert(
+                mint_keys[from_index],
+                to_indices.into_iter().map(|i| mint_keys[i]).collect(),
+            );
+        }
 
-    let request = SwapRequest::new(keypair.pubkey(), quotes.clone(), user_token_out);
+        Ok(route_map)
+    }
+}
 
-    let Swap {
-        mut swap_transaction,
-        last_valid_block_height: _,
-    } = swap(request)
-        .await
-        .map_err(|e| format!("Swap failed: {}", e))?;
+/// Jupiter aggregator on Solana
+pub struct JupiterRouter;
+
+// Comma separated DEX labels from env, e.g. JUPITER_EXCLUDE_DEXES="Obric V2,Saber"
+fn dex_labels(var: &str) -> Option<Vec<String>> {
+    let labels: Vec<String> = env::var(var)
+        .ok()?
+        .split(',')
+        .map(|label| label.trim().to_string())
+        .filter(|label| !label.is_empty())
+        .collect();
+    (!labels.is_empty()).then_some(labels)
+}
 
-    // Get the latest blockhash
-    let recent_blockhash_for_swap: Hash = rpc_client
-        .get_latest_blockhash()
+pub async fn jupiter_quote(params: &SwapParams) -> core::result::Result<Quote, String> {
+    let amount = params
+        .amount
+        .to_u64()
+        .ok_or_else(|| format!("Invalid amount: {}", params.amount))?;
+    let swap_mode = match params.side {
+        SwapSide::ExactIn => SwapMode::ExactIn,
+        SwapSide::ExactOut => SwapMode::ExactOut,
+    };
+
+    JUPITER_CLIENT
+        .quote(
+            Pubkey::from_str(&params.token_in).map_err(|e| format!("Invalid token_in: {}", e))?,
+            Pubkey::from_str(&params.token_out).map_err(|e| format!("Invalid token_out: {}", e))?,
+            amount,
+            QuoteConfig {
+                only_direct_routes: Some(false),
+                swap_mode: Some(swap_mode),
+                slippage_bps: Some(params.slippage_bps),
+                dexes: dex_labels("JUPITER_DEXES"),
+                exclude_dexes: dex_labels("JUPITER_EXCLUDE_DEXES"),
+                ..QuoteConfig::default()
+            },
+        )
         .await
-        .map_err(|e| format!("Failed to get latest blockhash: {}", e))?;
-    swap_transaction
-        .message
-        .set_recent_blockhash(recent_blockhash_for_swap);
+        .map_err(|e| format!("Failed to get quotes: {}", e))
+}
 
-    // Sign the swap transaction
-    let swap_transaction = VersionedTransaction::try_new(swap_transaction.message, &[&keypair])
-        .map_err(|e| format!("Failed to create signed transaction: {}", e))?;
+impl SwapRouter for JupiterRouter {
+    fn name(&self) -> &'static str {
+        "jupiter"
+    }
 
-    // Simulate the transaction before sending
-    rpc_client
-        .simulate_transaction(&swap_transaction)
-        .await
-        .map_err(|e| format!("Transaction simulation failed: {}", e))?;
+    fn supported_chain(&self) -> &'static str {
+        "solana"
+    }
 
-    // Send and confirm the transaction
-    rpc_client
-        .send_and_confirm_transaction_with_spinner(&swap_transaction)
-        .await
-        .map_err(|e| format!("Transaction failed: {}", e))?;
+    fn quote<'a>(
+        &'a self,
+        params: &'a SwapParams,
+    ) -> BoxFuture<'a, core::result::Result<SwapQuote, String>> {
+        Box::pin(async move {
+            let quote = jupiter_quote(params).await?;
+
+            Ok(SwapQuote {
+                router: self.name(),
+                amount_in: BigInt::from(quote.in_amount),
+                amount_out: BigInt::from(quote.out_amount),
+                gas_cost_usd: 0.0,
+            })
+        })
+    }
 
-    Ok(())
+    fn build_swap<'a>(
+        &'a self,
+        params: &'a SwapParams,
+    ) -> BoxFuture<'a, core::result::Result<SwapTransaction, String>> {
+        Box::pin(async move {
+            let quote = jupiter_quote(params).await?;
+
+            let sender =
+                Pubkey::from_str(&params.sender).map_err(|e| format!("Invalid sender: {}", e))?;
+            let receiver = Pubkey::from_str(&params.receiver)
+                .map_err(|e| format!("Invalid receiver: {}", e))?;
+            let receiver_token_out = get_associated_token_address(&receiver, &quote.output_mint);
+
+            let request = SwapRequest::new(sender, quote, receiver_token_out);
+            let Swap {
+                swap_transaction,
+                last_valid_block_height: _,
+            } = JUPITER_CLIENT
+                .swap(request)
+                .await
+                .map_err(|e| format!("Swap failed: {}", e))?;
+
+            Ok(SwapTransaction::Solana(swap_transaction))
+        })
+    }
 }
 
 pub async fn create_token_account(
@@ -562,3 +688,67 @@ pub async fn create_token_account(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(config: &QuoteConfig) -> String {
+        reqwest::Client::new()
+            .get("https://quote-api.jup.ag/v6/quote")
+            .query(config)
+            .build()
+            .unwrap()
+            .url()
+            .query()
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    #[test]
+    fn quote_config_leaves_unset_parameters_out() {
+        assert_eq!(query(&QuoteConfig::default()), "");
+    }
+
+    #[test]
+    fn quote_config_encodes_camel_case_parameters() {
+        let config = QuoteConfig {
+            slippage_bps: Some(50),
+            swap_mode: Some(SwapMode::ExactOut),
+            only_direct_routes: Some(false),
+            compute_auto_slippage: Some(true),
+            max_accounts: Some(64),
+            ..QuoteConfig::default()
+        };
+        assert_eq!(
+            query(&config),
+            "slippageBps=50&computeAutoSlippage=true&swapMode=ExactOut&onlyDirectRoutes=false&maxAccounts=64"
+        );
+    }
+
+    #[test]
+    fn quote_config_joins_dex_labels_with_commas() {
+        let config = QuoteConfig {
+            dexes: Some(vec!["Raydium".to_string(), "Orca V2".to_string()]),
+            exclude_dexes: Some(vec!["Obric V2".to_string()]),
+            ..QuoteConfig::default()
+        };
+        assert_eq!(
+            query(&config),
+            "dexes=Raydium%2COrca+V2&excludeDexes=Obric+V2"
+        );
+    }
+
+    #[test]
+    fn swap_transaction_decoding_fails_without_panicking() {
+        assert!(matches!(
+            decode_transaction("not base64!".to_string()),
+            Err(Error::Base64(_))
+        ));
+        // valid base64, not a transaction
+        assert!(matches!(
+            decode_transaction("AA==".to_string()),
+            Err(Error::Bincode(_))
+        ));
+    }
+}
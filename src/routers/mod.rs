@@ -1,237 +1,570 @@
 pub mod jupiter;
 pub mod paraswap;
+pub mod quote_cache;
+pub mod swap_router;
+pub mod uniswap_v3;
+pub mod zeroex;
 
 // use ethers::providers::Middleware;
 // use ethers::prelude::*;
 use serde_json::Value;
 use crate::chains::*;
 use crate::PostIntentInfo;
-use ethereum::ethereum_chain::{ethereum_simulate_swap, fetch_eth_gas_price};
+use ethereum::ethereum_chain::{evm_network, fetch_eth_gas_price, EvmNetwork};
 use lazy_static::lazy_static;
 use num_bigint::BigInt;
 use num_traits::ToPrimitive;
-use solana::solana_chain::solana_simulate_swap;
+use quote_cache::QuoteCache;
+use solana::solana_chain::{
+    count_missing_token_accounts, fetch_solana_priority_fee, fetch_token_account_rent,
+    solana_tx_fee, ATOMIC_SWAP_COMPUTE_UNITS, SEND_FUNDS_TO_USER_COMPUTE_UNITS,
+};
+use swap_router::{best_quote, select_router, SwapParams, SwapQuote, SwapSide};
 use std::collections::HashMap;
 use std::env;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::RwLock;
 
 // Constants for gas usage and costs
 const STORE_INTENT_GAS: f64 = 250_000f64;
 const SEND_FUNDS_TO_USER_GAS: f64 = 170_000f64;
 const ON_RECEIVE_TRANSFER_GAS: f64 = 150_000f64;
-// const ETH_TO_SOL_BRIDGE_FEE: f64 = 0.05; // in SOL
+
+// Constants for Solana compute usage and costs
+const STORE_INTENT_COMPUTE_UNITS: u32 = 200_000;
+const ON_RECEIVE_TRANSFER_COMPUTE_UNITS: u32 = 200_000;
+const DEFAULT_SOLANA_RELAYER_FEE: f64 = 0.05; // in SOL
+const DEFAULT_FLAT_FEES_TTL: u64 = 60; // in seconds
+
+// Limits on the quote a bid was based on when the intent is executed
+const DEFAULT_QUOTE_MAX_AGE: u64 = 120; // in seconds
+const DEFAULT_QUOTE_MAX_DRIFT_BPS: u64 = 50;
 
 // Struct to hold fee information
 #[derive(Debug, Clone)]
 pub struct FeeInfo {
     // In bridge token
-    store_intent: f64, // called on des chian
-    send_funds_to_user: f64, // called on des chian
-    on_receive_transfer: f64, // when cross chain, called on src chain
-    relayer_fee: f64, // when cross chain, send message des -> src
+    pub store_intent: f64, // called on des chian
+    pub send_funds_to_user: f64, // called on des chian
+    pub swap_and_send_funds_to_user: f64, // atomic swap + send on des chain, Solana only
+    pub on_receive_transfer: f64, // when cross chain, called on src chain
+    pub relayer_fee: f64, // when cross chain, send message des -> src
+    pub token_account_rent: f64, // per token account the solver creates on des chain
     // rollup_fee: f64,
 }
 
+impl FeeInfo {
+    pub fn total(&self, new_token_accounts: u32) -> f64 {
+        self.store_intent + self.send_funds_to_user + self.on_receive_transfer + self.relayer_fee
+            + self.token_account_rent * new_token_accounts as f64
+    }
+}
+
+// Simulated amounts of an intent, in the smallest unit of each token
+#[derive(Debug, Clone)]
+pub struct QuoteResult {
+    pub src_amount_out: BigInt, // token_in swapped to bridge token on src chain
+    pub fees: FeeInfo,
+    pub new_token_accounts: u32,
+    pub flat_fee: BigInt, // fees.total(new_token_accounts) in bridge token
+    pub bridge_amount: BigInt, // src_amount_out - flat_fee, swapped to token_out on des chain
+    pub commission: BigInt, // bridge token kept by the solver, set by the bid strategy
+    pub dst_amount_out: BigInt, // token_out for bridge_amount, before commission
+    pub dst_bridge_token: String, // bridge token address on des chain, swapped to token_out
+    pub routes: Vec<String>,
+    pub src_router: Option<&'static str>, // router to execute token_in -> bridge token with
+    pub dst_router: Option<&'static str>, // router to execute bridge token -> token_out with
+    pub quoted_at: SystemTime,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum QuoteError {
+    #[error("operation not supported: {0}")]
+    UnsupportedOperation(String),
+
+    #[error("chain not supported: {0}")]
+    UnsupportedChain(String),
+
+    #[error("token {token} not supported on {chain}")]
+    UnsupportedToken { token: String, chain: String },
+
+    #[error("invalid amount: {0}")]
+    InvalidAmount(String),
+
+    #[error("{router} quote failed: {error}")]
+    Router { router: String, error: String },
+
+    #[error("flat fees: {0}")]
+    FlatFee(String),
+
+    #[error("swap size {amount} does not cover FLAT_FEES {flat_fee}")]
+    BelowFlatFee { amount: BigInt, flat_fee: BigInt },
+
+    #[error("quote is {age}s old, above QUOTE_MAX_AGE {max_age}s")]
+    Stale { age: u64, max_age: u64 },
+
+    #[error("{needed} bridge token needed for the winning amount, {budgeted} budgeted ({quoted} quoted + QUOTE_MAX_DRIFT_BPS {max_drift_bps})")]
+    Drift {
+        needed: BigInt,
+        budgeted: BigInt,
+        quoted: BigInt,
+        max_drift_bps: u64,
+    },
+}
+
 lazy_static! {
-    pub static ref FLAT_FEES: Arc<RwLock<HashMap<(String, String), FeeInfo>>> = Arc::new(RwLock::new(HashMap::new()));
+    // <(src chain, dst chain), (updated at, fees)>
+    pub static ref FLAT_FEES: Arc<RwLock<HashMap<(String, String), (Instant, FeeInfo)>>> = Arc::new(RwLock::new(HashMap::new()));
+    static ref FLAT_FEES_TTL: Duration = Duration::from_secs(
+        env::var("FLAT_FEES_TTL")
+            .ok()
+            .and_then(|ttl| ttl.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_FLAT_FEES_TTL)
+    );
+    pub static ref QUOTE_CACHE: QuoteCache = QuoteCache::from_env();
+    // <intent_id, QuoteResult> of the intents we bid on
+    pub static ref QUOTES: Arc<RwLock<HashMap<String, QuoteResult>>> = Arc::new(RwLock::new(HashMap::new()));
 }
 
-async fn fetch_eth_price() -> Result<f64, Box<dyn std::error::Error>> {
-    let url = "https://api.coingecko.com/api/v3/simple/price?ids=ethereum&vs_currencies=usd";
-    let response: Value = reqwest::get(url).await.unwrap().json().await.unwrap();
-    let eth_price = response["ethereum"]["usd"].as_f64().unwrap().round();
-    Ok(eth_price)
+// Price of the token paying gas on an EVM network
+async fn fetch_native_price(coingecko_id: &str) -> Result<f64, Box<dyn std::error::Error>> {
+    let url = format!(
+        "https://api.coingecko.com/api/v3/simple/price?ids={coingecko_id}&vs_currencies=usd"
+    );
+    let response: Value = reqwest::get(url).await?.json().await?;
+    let native_price = response[coingecko_id]["usd"]
+        .as_f64()
+        .ok_or("Missing price in coingecko response")?;
+    Ok(native_price)
 }
 
 async fn fetch_sol_price() -> Result<f64, Box<dyn std::error::Error>> {
     let url = "https://api.coingecko.com/api/v3/simple/price?ids=solana&vs_currencies=usd";
-    let response: Value = reqwest::get(url).await.unwrap().json().await.unwrap();
-    let sol_price = response["solana"]["usd"].as_f64().unwrap().round();
+    let response: Value = reqwest::get(url).await?.json().await?;
+    let sol_price = response["solana"]["usd"]
+        .as_f64()
+        .ok_or("Missing price in coingecko response")?
+        .round();
     Ok(sol_price)
 }
 
-pub async fn update_flat_fees() -> Result<(), Box<dyn std::error::Error>> {
-    let mut fees = FLAT_FEES.write().await;
+// Fees of the Solana side of an intent, in USD
+#[derive(Debug, Clone)]
+struct SolanaFees {
+    store_intent: f64,
+    send_funds_to_user: f64,
+    swap_and_send_funds_to_user: f64,
+    on_receive_transfer: f64,
+    token_account_rent: f64,
+    relayer_fee: f64,
+}
 
-    let eth_gas_price = fetch_eth_gas_price()
+async fn fetch_solana_fees() -> Result<SolanaFees, String> {
+    let sol_price = fetch_sol_price()
+        .await
+        .map_err(|e| format!("Failed to fetch sol price: {}", e))?;
+    let sol_priority_fee = fetch_solana_priority_fee()
         .await
-        .map_err(|e| format!("Failed to fetch gas price: {}", e))?;
-    println!("Updated Gas_FEES: {:?}", eth_gas_price);
-    let priority_fee_per_gas: u128 = 500_000_000; // This is already in wei
-    let max_fee_per_gas = (eth_gas_price + priority_fee_per_gas).as_u128() as f64;
+        .map_err(|e| format!("Failed to fetch solana priority fee: {}", e))?;
+    let sol_token_account_rent = fetch_token_account_rent()
+        .await
+        .map_err(|e| format!("Failed to fetch token account rent: {}", e))?;
+    let lamports_to_usd = |lamports: u64| lamports as f64 * sol_price / 1e9;
+
+    // Cross-domain fees
+    let sol_relayer_fee = env::var("SOLANA_RELAYER_FEE")
+        .ok()
+        .and_then(|fee| fee.parse::<f64>().ok())
+        .unwrap_or(DEFAULT_SOLANA_RELAYER_FEE);
 
-    let eth_price = fetch_eth_price()
+    Ok(SolanaFees {
+        store_intent: lamports_to_usd(solana_tx_fee(STORE_INTENT_COMPUTE_UNITS, sol_priority_fee)),
+        send_funds_to_user: lamports_to_usd(solana_tx_fee(
+            SEND_FUNDS_TO_USER_COMPUTE_UNITS,
+            sol_priority_fee,
+        )),
+        swap_and_send_funds_to_user: lamports_to_usd(solana_tx_fee(
+            ATOMIC_SWAP_COMPUTE_UNITS,
+            sol_priority_fee,
+        )),
+        on_receive_transfer: lamports_to_usd(solana_tx_fee(
+            ON_RECEIVE_TRANSFER_COMPUTE_UNITS,
+            sol_priority_fee,
+        )),
+        token_account_rent: lamports_to_usd(sol_token_account_rent),
+        relayer_fee: sol_relayer_fee * sol_price,
+    })
+}
+
+// Fees of the EVM side of an intent, in USD
+#[derive(Debug, Clone)]
+struct EvmFees {
+    store_intent: f64,
+    send_funds_to_user: f64,
+    on_receive_transfer: f64,
+}
+
+async fn fetch_evm_fees(network: &EvmNetwork) -> Result<EvmFees, String> {
+    // EVM networks the solver can fill on, i.e. with an RPC and an escrow
+    if network.rpc_url().is_err() || network.escrow_address().is_none() {
+        return Err(format!("{} has no RPC or escrow configured", network.name));
+    }
+
+    let gas_price = fetch_eth_gas_price(network)
         .await
-        .map_err(|e| format!("Failed to fetch eth price: {}", e))?;
-    let sol_price = fetch_sol_price()
+        .map_err(|e| format!("Failed to fetch {} gas price: {}", network.name, e))?;
+    println!("Updated {} Gas_FEES: {:?}", network.name, gas_price);
+    let max_fee_per_gas = (gas_price + network.priority_fee_per_gas).as_u128() as f64;
+
+    let native_price = fetch_native_price(network.native_coingecko_id)
         .await
-        .map_err(|e| format!("Failed to fetch sol price: {}", e))?;
-    // Ethereum single-domain fees
-    let eth_store_intent = STORE_INTENT_GAS * max_fee_per_gas * eth_price / 1e18;
-    let eth_send_funds = SEND_FUNDS_TO_USER_GAS * max_fee_per_gas * eth_price / 1e18;
-    let eth_on_receive = ON_RECEIVE_TRANSFER_GAS * max_fee_per_gas * eth_price / 1e18;
+        .map_err(|e| format!("Failed to fetch {} price: {}", network.native_coingecko_id, e))?;
+    let gas_to_usd = |gas: f64| gas * max_fee_per_gas * native_price / 1e18;
 
-    // Solana single-domain fees
-    // let sol_store_intent = 0.008;
-    // let sol_send_funds = 0.008;
+    Ok(EvmFees {
+        store_intent: gas_to_usd(STORE_INTENT_GAS),
+        send_funds_to_user: gas_to_usd(SEND_FUNDS_TO_USER_GAS),
+        on_receive_transfer: gas_to_usd(ON_RECEIVE_TRANSFER_GAS),
+    })
+}
 
-    // Cross-domain fees
-    // let eth_to_sol_on_receive = 0.008;
- 
-    fees.insert(
-        ("ethereum".to_string(), "ethereum".to_string()),
-        FeeInfo {
-            store_intent: eth_store_intent,
-            send_funds_to_user: eth_send_funds,
-            on_receive_transfer: 0.0,
-            relayer_fee: 0.0,
+// Fetches the fees of the chains an intent touches, only those
+async fn fetch_fee_info(src_chain: &str, dst_chain: &str) -> Result<FeeInfo, String> {
+    match (evm_network(src_chain), evm_network(dst_chain)) {
+        // Solana single-domain fees
+        (None, None) if src_chain == "solana" && dst_chain == "solana" => {
+            let sol = fetch_solana_fees().await?;
+            Ok(FeeInfo {
+                store_intent: sol.store_intent,
+                send_funds_to_user: sol.send_funds_to_user,
+                swap_and_send_funds_to_user: sol.swap_and_send_funds_to_user,
+                on_receive_transfer: 0.0,
+                relayer_fee: 0.0,
+                token_account_rent: sol.token_account_rent,
+            })
         }
-    );
-
-    fees.insert(
-        ("solana".to_string(), "solana".to_string()),
-        FeeInfo {
-            store_intent: 0.008,
-            send_funds_to_user: 0.008,
-            on_receive_transfer: 0.0,
-            relayer_fee: 0.0,
+        // EVM single-domain fees
+        (Some(src), Some(dst)) if src.name == dst.name => {
+            let evm = fetch_evm_fees(dst).await?;
+            Ok(FeeInfo {
+                store_intent: evm.store_intent,
+                send_funds_to_user: evm.send_funds_to_user,
+                swap_and_send_funds_to_user: evm.send_funds_to_user,
+                on_receive_transfer: 0.0,
+                relayer_fee: 0.0,
+                token_account_rent: 0.0,
+            })
         }
-    );
-
-    fees.insert(
-        ("ethereum".to_string(), "solana".to_string()),
-        FeeInfo {
-            store_intent: 0.008,
-            send_funds_to_user: 0.008,
-            on_receive_transfer: eth_on_receive,
-            relayer_fee: 0.0,
+        (Some(src), None) if dst_chain == "solana" => {
+            let (evm, sol) = futures::try_join!(fetch_evm_fees(src), fetch_solana_fees())?;
+            Ok(FeeInfo {
+                store_intent: sol.store_intent,
+                send_funds_to_user: sol.send_funds_to_user,
+                swap_and_send_funds_to_user: sol.swap_and_send_funds_to_user,
+                on_receive_transfer: evm.on_receive_transfer,
+                relayer_fee: 0.0,
+                token_account_rent: sol.token_account_rent,
+            })
         }
-    );
+        (None, Some(dst)) if src_chain == "solana" => {
+            let (evm, sol) = futures::try_join!(fetch_evm_fees(dst), fetch_solana_fees())?;
+            Ok(FeeInfo {
+                store_intent: evm.store_intent,
+                send_funds_to_user: evm.send_funds_to_user,
+                swap_and_send_funds_to_user: evm.send_funds_to_user,
+                on_receive_transfer: sol.on_receive_transfer,
+                relayer_fee: sol.relayer_fee,
+                token_account_rent: 0.0,
+            })
+        }
+        _ => Err(format!("No flat fees for {src_chain} -> {dst_chain}")),
+    }
+}
 
-    fees.insert(
-        ("solana".to_string(), "ethereum".to_string()),
-        FeeInfo {
-            store_intent: eth_store_intent,
-            send_funds_to_user: eth_send_funds,
-            on_receive_transfer: 0.008,
-            relayer_fee: 0.05 * sol_price,
+/// FLAT_FEES of an intent from `src_chain` to `dst_chain`, fetched again once
+/// older than `FLAT_FEES_TTL` seconds. The fetch runs without holding the
+/// lock, and a failed one falls back to the last fees known for the pair.
+pub async fn get_fee_info(src_chain: &str, dst_chain: &str) -> Result<FeeInfo, Box<dyn std::error::Error>> {
+    let key = (src_chain.to_string(), dst_chain.to_string());
+    let cached = FLAT_FEES.read().await.get(&key).cloned();
+    if let Some((updated_at, fee_info)) = &cached {
+        if updated_at.elapsed() < *FLAT_FEES_TTL {
+            return Ok(fee_info.clone());
         }
-    );
+    }
 
-    println!("Updated FLAT_FEES: {:?}", *fees);
-    Ok(())
+    match fetch_fee_info(src_chain, dst_chain).await {
+        Ok(fee_info) => {
+            println!("Updated FLAT_FEES {src_chain} -> {dst_chain}: {:?}", fee_info);
+            FLAT_FEES
+                .write()
+                .await
+                .insert(key, (Instant::now(), fee_info.clone()));
+            Ok(fee_info)
+        }
+        Err(e) => match cached {
+            Some((_, fee_info)) => {
+                eprintln!("Error updating flat fees {src_chain} -> {dst_chain}, using the last ones: {e}");
+                Ok(fee_info)
+            }
+            None => Err(e.into()),
+        },
+    }
 }
 
-pub async fn get_flat_fee(src_chain: &str, dst_chain: &str) -> Result<f64, Box<dyn std::error::Error>> {
-    let fees = FLAT_FEES.read().await;
-    let fee_info = fees.get(&(src_chain.to_string(), dst_chain.to_string()))
-        .ok_or("Fee information not found for the given chain pair")?;
+// Token accounts the solver pays rent for when filling the intent on Solana
+async fn get_new_token_accounts(
+    src_chain: &str,
+    dst_chain: &str,
+    token_in: &str,
+    token_out: &str,
+    dst_chain_user: &str,
+    bridge_token_address_dst: &str,
+) -> u32 {
+    if dst_chain != "solana" {
+        return 0;
+    }
+
+    let solver = SOLVER_ADDRESSES[1];
+    let mut owners_and_mints = vec![(dst_chain_user, token_out)];
+    if !bridge_token_address_dst.eq_ignore_ascii_case(token_out) {
+        owners_and_mints.push((solver, token_out));
+    }
+    if src_chain == dst_chain {
+        owners_and_mints.push((solver, token_in));
+    }
 
-    let total_fee = fee_info.store_intent + fee_info.send_funds_to_user + fee_info.on_receive_transfer + fee_info.relayer_fee;
-    Ok(total_fee)
+    match count_missing_token_accounts(&owners_and_mints).await {
+        Ok(count) => count,
+        Err(e) => {
+            eprintln!("Error counting missing token accounts: {:?}", e);
+            0
+        }
+    }
 }
 
-// pub async fn start_fee_updater() {
-//     tokio::spawn(async {
-//         loop {
-//             if let Err(e) = update_flat_fees().await {
-//                 eprintln!("Error updating flat fees: {:?}", e);
-//             }
-//             tokio::time::sleep(tokio::time::Duration::from_secs(300)).await; // Update every 5 minutes
-//         }
-//     });
-// }
+async fn simulate_swap(
+    chain: &str,
+    token_in: &str,
+    token_out: &str,
+    amount_in: &BigInt,
+    bridge_token_in: bool,
+    routes: &mut Vec<String>,
+) -> Result<SwapQuote, QuoteError> {
+    if let Some(quote) = QUOTE_CACHE.get(chain, token_in, token_out, amount_in).await {
+        routes.push(format!("{chain}:{} (cached)", quote.router));
+        return Ok(quote);
+    }
+
+    let solver = get_solver_address(chain)
+        .ok_or_else(|| QuoteError::UnsupportedChain(chain.to_string()))?;
+    let params = SwapParams {
+        token_in: token_in.to_string(),
+        token_out: token_out.to_string(),
+        amount: amount_in.clone(),
+        side: SwapSide::ExactIn,
+        sender: solver.to_string(),
+        receiver: solver.to_string(),
+        slippage_bps: 100,
+    };
+
+    let quote = best_quote(chain, &params, bridge_token_in)
+        .await
+        .map_err(|error| QuoteError::Router {
+            router: chain.to_string(),
+            error,
+        })?;
+    routes.push(format!("{chain}:{}", quote.router));
+
+    QUOTE_CACHE.insert(chain, token_in, token_out, &quote).await;
+    Ok(quote)
+}
 
 pub async fn get_simulate_swap_intent(
     intent_info: &PostIntentInfo,
     src_chain: &str,
     dst_chain: &str,
     bridge_token: &String,
-) -> String {
+) -> Result<QuoteResult, QuoteError> {
     // Extracting values from OperationInput
     let (token_in, amount_in) = match &intent_info.inputs {
         OperationInput::SwapTransfer(input) => (input.token_in.clone(), input.amount_in.clone()),
-        OperationInput::Lend(_) => todo!(),
-        OperationInput::Borrow(_) => todo!(),
+        _ => return Err(QuoteError::UnsupportedOperation(intent_info.function_name.clone())),
     };
 
-    let (dst_chain_user, token_out, _) = match &intent_info.outputs {
-        OperationOutput::SwapTransfer(output) => (
-            output.dst_chain_user.clone(),
-            output.token_out.clone(),
-            output.amount_out.clone(),
-        ),
-        OperationOutput::Lend(_) => todo!(),
-        OperationOutput::Borrow(_) => todo!(),
+    let (dst_chain_user, token_out) = match &intent_info.outputs {
+        OperationOutput::SwapTransfer(output) => {
+            (output.dst_chain_user.clone(), output.token_out.clone())
+        }
+        _ => return Err(QuoteError::UnsupportedOperation(intent_info.function_name.clone())),
     };
 
-    let (bridge_token_address_src, _) = get_token_info(bridge_token, src_chain).unwrap();
-    let mut amount_out_src_chain = BigInt::from_str(&amount_in).unwrap();
+    let unsupported_token = |chain: &str| QuoteError::UnsupportedToken {
+        token: bridge_token.clone(),
+        chain: chain.to_string(),
+    };
+    let (bridge_token_address_src, _) =
+        get_token_info(bridge_token, src_chain).ok_or_else(|| unsupported_token(src_chain))?;
+    let (bridge_token_address_dst, _) =
+        get_token_info(bridge_token, dst_chain).ok_or_else(|| unsupported_token(dst_chain))?;
+
+    let amount_in =
+        BigInt::from_str(&amount_in).map_err(|_| QuoteError::InvalidAmount(amount_in.clone()))?;
+    let mut routes = Vec::new();
+    let mut src_router = None;
+    let mut dst_router = None;
 
-    if !bridge_token_address_src.eq_ignore_ascii_case(&token_in) {
+    let src_amount_out = if !bridge_token_address_src.eq_ignore_ascii_case(&token_in) {
         // simulate token_in -> bridge token
-        if src_chain == "ethereum" {
-            amount_out_src_chain =
-                ethereum_simulate_swap(&token_in, &amount_in, bridge_token_address_src).await;
-        } else if src_chain == "solana" {
-            amount_out_src_chain = BigInt::from_str(
-                &solana_simulate_swap(
-                    &dst_chain_user,
-                    &token_in,
-                    &bridge_token_address_src,
-                    BigInt::from_str(&amount_in).unwrap().to_u64().unwrap(),
-                )
-                .await,
-            )
-            .unwrap();
-        }
+        let quote = simulate_swap(
+            src_chain,
+            &token_in,
+            bridge_token_address_src,
+            &amount_in,
+            false,
+            &mut routes,
+        )
+        .await?;
+        src_router = Some(quote.router);
+        quote.amount_out
+    } else {
+        amount_in
+    };
+
+    // get flat fees, refreshed for this chain pair when outdated
+    let new_token_accounts = get_new_token_accounts(
+        src_chain,
+        dst_chain,
+        &token_in,
+        &token_out,
+        &dst_chain_user,
+        bridge_token_address_dst,
+    )
+    .await;
+    let mut fees = get_fee_info(src_chain, dst_chain)
+        .await
+        .map_err(|e| QuoteError::FlatFee(e.to_string()))?;
+    // Solana fills swap intents in one transaction with the Jupiter swap,
+    // see handle_solana_execution, priced with its larger compute budget
+    if dst_chain == "solana"
+        && intent_info.function_name == "swap"
+        && !bridge_token_address_dst.eq_ignore_ascii_case(&token_out)
+    {
+        fees.send_funds_to_user = fees.swap_and_send_funds_to_user;
     }
+    let flat_fee = BigInt::from((fees.total(new_token_accounts) * 1e6).round().to_u128().unwrap());
 
-    let (bridge_token_address_dst, _) = get_token_info(bridge_token, dst_chain).unwrap();
+    if src_amount_out <= flat_fee {
+        return Err(QuoteError::BelowFlatFee {
+            amount: src_amount_out,
+            flat_fee,
+        });
+    }
 
-    // update the flat fee
-    if let Err(e) = update_flat_fees().await {
-            eprintln!("Error updating flat fees: {:?}", e);
-        }
-    // get flat fees
-    let flat_fee = get_flat_fee(src_chain, dst_chain).await.unwrap();
-    let flat_fee_int = (flat_fee * 1e6).round().to_u128().unwrap();
-    // get comission
-    let comission = env::var("COMISSION")
-        .expect("COMISSION must be set")
-        .parse::<u32>()
-        .unwrap();
-    
-    let total_fee = flat_fee_int + (amount_out_src_chain.clone() * BigInt::from(comission) / BigInt::from(100_000));
-    if amount_out_src_chain < total_fee {
-        return String::from("0");
-    }
-
-    // we substract the flat fees and the solver comission in USD
-    let amount_in_dst_chain = amount_out_src_chain - total_fee;
-    let mut final_amount_out = amount_in_dst_chain.to_string();
-
-    if !bridge_token_address_dst.eq_ignore_ascii_case(&token_out) {
+    // we substract the flat fees in USD, the solver commission is left to the bid strategy
+    let bridge_amount = &src_amount_out - &flat_fee;
+
+    let dst_amount_out = if !bridge_token_address_dst.eq_ignore_ascii_case(&token_out) {
         // simulate USDT -> token_out
-        if dst_chain == "ethereum" {
-            final_amount_out =
-                ethereum_simulate_swap(bridge_token_address_src, &final_amount_out, &token_out)
-                    .await
-                    .to_string();
-        } else if dst_chain == "solana" {
-            final_amount_out = solana_simulate_swap(
-                &dst_chain_user,
-                bridge_token_address_dst,
-                &token_out,
-                amount_in_dst_chain.to_u64().unwrap(),
-            )
-            .await;
-        }
+        let quote = simulate_swap(
+            dst_chain,
+            bridge_token_address_dst,
+            &token_out,
+            &bridge_amount,
+            true,
+            &mut routes,
+        )
+        .await?;
+        dst_router = Some(quote.router);
+        quote.amount_out
+    } else {
+        bridge_amount.clone()
+    };
+
+    Ok(QuoteResult {
+        src_amount_out,
+        fees,
+        new_token_accounts,
+        flat_fee,
+        bridge_amount,
+        commission: BigInt::from(0),
+        dst_amount_out,
+        dst_bridge_token: bridge_token_address_dst.to_string(),
+        routes,
+        src_router,
+        dst_router,
+        quoted_at: SystemTime::now(),
+    })
+}
+
+/// Checks, before executing a won intent, that the quote the bid was based
+/// on still holds: it is at most `QUOTE_MAX_AGE` seconds old and buying the
+/// winning `amount` of token_out on the destination chain costs at most
+/// `QUOTE_MAX_DRIFT_BPS` more bridge token than quoted.
+pub async fn check_quote_drift(
+    intent_info: &PostIntentInfo,
+    amount: &str,
+    quote: &QuoteResult,
+) -> Result<(), QuoteError> {
+    let env_u64 = |name: &str, default: u64| {
+        env::var(name)
+            .ok()
+            .and_then(|value| value.parse::<u64>().ok())
+            .unwrap_or(default)
+    };
+    let max_age = env_u64("QUOTE_MAX_AGE", DEFAULT_QUOTE_MAX_AGE);
+    let max_drift_bps = env_u64("QUOTE_MAX_DRIFT_BPS", DEFAULT_QUOTE_MAX_DRIFT_BPS);
+
+    let age = quote.quoted_at.elapsed().unwrap_or_default().as_secs();
+    if age > max_age {
+        return Err(QuoteError::Stale { age, max_age });
     }
 
-    final_amount_out
+    let token_out = match &intent_info.outputs {
+        OperationOutput::SwapTransfer(output) => output.token_out.clone(),
+        _ => return Err(QuoteError::UnsupportedOperation(intent_info.function_name.clone())),
+    };
+    let dst_chain = intent_info.dst_chain.as_str();
+    // the bridge token the quote swapped from, not necessarily USDT
+    let bridge_token = quote.dst_bridge_token.as_str();
+    let amount =
+        BigInt::from_str(amount).map_err(|_| QuoteError::InvalidAmount(amount.to_string()))?;
+
+    let needed = if bridge_token.eq_ignore_ascii_case(&token_out) {
+        amount
+    } else {
+        // what the execution will pay for the same swap, with the same router
+        let solver = get_solver_address(dst_chain)
+            .ok_or_else(|| QuoteError::UnsupportedChain(dst_chain.to_string()))?;
+        let params = SwapParams {
+            token_in: bridge_token.to_string(),
+            token_out,
+            amount,
+            side: SwapSide::ExactOut,
+            sender: solver.to_string(),
+            receiver: solver.to_string(),
+            slippage_bps: 100,
+        };
+        let router_error = |error: String| QuoteError::Router {
+            router: dst_chain.to_string(),
+            error,
+        };
+        let router = select_router(dst_chain, quote.dst_router).map_err(router_error)?;
+        router.quote(&params).await.map_err(router_error)?.amount_in
+    };
+
+    let budgeted = &quote.bridge_amount * (10_000 + max_drift_bps) / 10_000;
+    if needed > budgeted {
+        return Err(QuoteError::Drift {
+            needed,
+            budgeted,
+            quoted: quote.bridge_amount.clone(),
+            max_drift_bps,
+        });
+    }
+    Ok(())
 }
 
 // Calculation ethereum gas fees
@@ -244,3 +577,89 @@ pub async fn get_simulate_swap_intent(
 
 // let profit = (amount_out_src_chain.to_f64().unwrap() / 10f64.powi(bridge_token_dec_src as i32))
 //     - (amount_in_dst_chain.to_f64().unwrap() / 10f64.powi(bridge_token_dec_dst as i32));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USDC: &str = "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48";
+
+    fn quote(bridge_amount: u64, age: Duration) -> QuoteResult {
+        QuoteResult {
+            src_amount_out: BigInt::from(bridge_amount),
+            fees: FeeInfo {
+                store_intent: 0.0,
+                send_funds_to_user: 0.0,
+                swap_and_send_funds_to_user: 0.0,
+                on_receive_transfer: 0.0,
+                relayer_fee: 0.0,
+                token_account_rent: 0.0,
+            },
+            new_token_accounts: 0,
+            flat_fee: BigInt::from(0),
+            bridge_amount: BigInt::from(bridge_amount),
+            commission: BigInt::from(0),
+            dst_amount_out: BigInt::from(bridge_amount),
+            dst_bridge_token: USDC.to_string(),
+            routes: Vec::new(),
+            src_router: None,
+            dst_router: None,
+            quoted_at: SystemTime::now() - age,
+        }
+    }
+
+    // pays out the quote's bridge token itself, so no router is asked
+    fn intent() -> PostIntentInfo {
+        PostIntentInfo {
+            function_name: "swap".to_string(),
+            src_chain: "ethereum".to_string(),
+            dst_chain: "ethereum".to_string(),
+            inputs: OperationInput::SwapTransfer(SwapTransferInput {
+                token_in: USDC.to_string(),
+                amount_in: "1000".to_string(),
+                src_chain_user: String::new(),
+                timeout: String::new(),
+            }),
+            outputs: OperationOutput::SwapTransfer(SwapTransferOutput {
+                token_out: USDC.to_lowercase(),
+                amount_out: "1000".to_string(),
+                dst_chain_user: String::new(),
+            }),
+        }
+    }
+
+    #[tokio::test]
+    async fn refuses_a_stale_quote() {
+        let quote = quote(1_000, Duration::from_secs(DEFAULT_QUOTE_MAX_AGE + 10));
+        match check_quote_drift(&intent(), "1000", &quote).await {
+            Err(QuoteError::Stale { max_age, .. }) => assert_eq!(max_age, DEFAULT_QUOTE_MAX_AGE),
+            other => panic!("expected a stale quote, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn allows_drift_up_to_the_budget() {
+        // 1000 quoted + 50 bps
+        let quote = quote(1_000_000, Duration::ZERO);
+        assert!(check_quote_drift(&intent(), "1005000", &quote).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn refuses_drift_past_the_budget_and_reports_it() {
+        let quote = quote(1_000_000, Duration::ZERO);
+        match check_quote_drift(&intent(), "1005001", &quote).await {
+            Err(QuoteError::Drift {
+                needed,
+                budgeted,
+                quoted,
+                max_drift_bps,
+            }) => {
+                assert_eq!(needed, BigInt::from(1_005_001));
+                assert_eq!(budgeted, BigInt::from(1_005_000));
+                assert_eq!(quoted, BigInt::from(1_000_000));
+                assert_eq!(max_drift_bps, DEFAULT_QUOTE_MAX_DRIFT_BPS);
+            }
+            other => panic!("expected drift, got {:?}", other),
+        }
+    }
+}
@@ -1,10 +1,36 @@
 pub mod paraswap_router {
-    use ethers::prelude::Address;
+    use crate::chains::ethereum::ethereum_chain::{
+        evm_context, get_evm_token_decimals, EvmNetwork, ERC20,
+    };
+    use crate::routers::jupiter::field_as_string;
+    use crate::routers::swap_router::{
+        SwapParams, SwapQuote, SwapRouter, SwapSide, SwapTransaction,
+    };
+    use ethers::prelude::{Address, Middleware, U256};
+    use futures::future::BoxFuture;
     use num_bigint::BigInt;
     use reqwest::Client;
+    use serde::de::DeserializeOwned;
+    use serde::Deserialize;
     use serde_json::Value;
+    use std::env;
     use std::str::FromStr;
 
+    const DEFAULT_MAX_IMPACT: u32 = 10; // in percent
+    const DEFAULT_PARTNER: &str = "paraswap.io";
+
+    #[derive(thiserror::Error, Debug)]
+    pub enum ParaswapError {
+        #[error("reqwest: {0}")]
+        Reqwest(#[from] reqwest::Error),
+
+        #[error("Paraswap API: {0}")]
+        ParaswapApi(String),
+
+        #[error("serde_json: {0}")]
+        SerdeJson(#[from] serde_json::Error),
+    }
+
     #[derive(Debug)]
     pub struct ParaswapParams {
         pub side: String,
@@ -16,108 +42,273 @@ pub mod paraswap_router {
         pub token1_decimals: u32,
         pub wallet_address: Address,
         pub receiver_address: Address,
+        pub slippage_bps: u64, // SELL: destAmount floor, BUY: srcAmount cap, around the quote
+        pub max_impact: u32, // in percent
+        pub gas_price: Option<u128>, // in wei, `PARASWAP_GAS_PRICE` or the network's
+        pub partner: String,
         pub client_aggregator: Client,
     }
 
+    /// `priceRoute` of `/prices`. Only the fields the solver reads are typed,
+    /// `raw` is posted back untouched to `/transactions`.
+    #[derive(Clone, Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct PriceRoute {
+        pub block_number: u64,
+        pub network: u64,
+        pub src_token: Address,
+        pub src_decimals: u32,
+        #[serde(with = "field_as_string")]
+        pub src_amount: BigInt,
+        pub dest_token: Address,
+        pub dest_decimals: u32,
+        #[serde(with = "field_as_string")]
+        pub dest_amount: BigInt,
+        #[serde(with = "field_as_string", rename = "srcUSD")]
+        pub src_usd: f64,
+        #[serde(with = "field_as_string", rename = "destUSD")]
+        pub dest_usd: f64,
+        #[serde(with = "field_as_string", rename = "gasCostUSD")]
+        pub gas_cost_usd: f64,
+        #[serde(with = "field_as_string")]
+        pub gas_cost: u64,
+        pub side: String,
+        pub token_transfer_proxy: Address,
+        pub contract_address: Address,
+        #[serde(skip)]
+        pub raw: Value,
+    }
+
+    /// Response of `/transactions`, the calldata to submit.
+    #[derive(Clone, Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct TransactionResponse {
+        pub from: Address,
+        pub to: Address,
+        #[serde(with = "field_as_string")]
+        pub value: u128,
+        pub data: String,
+        pub chain_id: u64,
+    }
+
+    fn maybe_paraswap_api_error<T>(value: Value) -> Result<T, ParaswapError>
+    where
+        T: DeserializeOwned,
+    {
+        #[derive(Deserialize)]
+        struct ErrorResponse {
+            error: String,
+        }
+        if let Ok(ErrorResponse { error }) = serde_json::from_value::<ErrorResponse>(value.clone()) {
+            Err(ParaswapError::ParaswapApi(error))
+        } else {
+            serde_json::from_value(value).map_err(|err| err.into())
+        }
+    }
+
+    pub async fn paraswap_price_route(params: &ParaswapParams) -> Result<PriceRoute, ParaswapError> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct PricesResponse {
+            price_route: Value,
+        }
+
+        let url = format!("https://apiv5.paraswap.io/prices?srcToken=0x{:x}&srcDecimals={}&destToken=0x{:x}&destDecimals={}&amount={}&side={}&network={}&maxImpact={}&partner={}",
+            params.token_in, params.token0_decimals, params.token_out, params.token1_decimals, params.amount_in, params.side, params.chain_id, params.max_impact, params.partner);
+
+        let response: PricesResponse = maybe_paraswap_api_error(
+            params.client_aggregator.get(url).send().await?.json().await?,
+        )?;
+
+        let mut price_route: PriceRoute = serde_json::from_value(response.price_route.clone())?;
+        price_route.raw = response.price_route;
+        Ok(price_route)
+    }
+
+    /// Least token_out a SELL may receive, `slippage_bps` below the quoted destAmount.
+    pub fn min_dest_amount(dest_amount: &BigInt, slippage_bps: u64) -> BigInt {
+        dest_amount * BigInt::from(10_000u64.saturating_sub(slippage_bps)) / BigInt::from(10_000)
+    }
+
+    /// Most token_in a BUY may spend, `slippage_bps` above the quoted srcAmount.
+    pub fn max_src_amount(src_amount: &BigInt, slippage_bps: u64) -> BigInt {
+        src_amount * BigInt::from(10_000 + slippage_bps) / BigInt::from(10_000)
+    }
+
+    pub async fn paraswap_transaction(
+        params: &ParaswapParams,
+        price_route: &PriceRoute,
+    ) -> Result<TransactionResponse, ParaswapError> {
+        let (amount_in, amount_out) = if params.side == "SELL" {
+            (
+                params.amount_in.clone(),
+                min_dest_amount(&price_route.dest_amount, params.slippage_bps),
+            )
+        } else {
+            (
+                max_src_amount(&price_route.src_amount, params.slippage_bps),
+                params.amount_in.clone(),
+            )
+        };
+
+        let mut url = format!(
+            "https://apiv5.paraswap.io/transactions/{}?ignoreChecks=true&ignoreGasEstimate=true&onlyParams=false", params.chain_id
+        );
+        if let Some(gas_price) = params.gas_price {
+            url.push_str(&format!("&gasPrice={}", gas_price));
+        }
+
+        let body = serde_json::json!({
+            "srcToken": format!("0x{:x}", params.token_in),
+            "destToken": format!("0x{:x}", params.token_out),
+            "srcAmount": format!("{}", amount_in),
+            "destAmount": format!("{}", amount_out),
+            "priceRoute": price_route.raw,
+            "userAddress": format!("0x{:x}", params.wallet_address),
+            "txOrigin": format!("0x{:x}", params.receiver_address),
+            //"receiver": format!("0x{:x}", *MY_SC),
+            "partner": params.partner,
+            "srcDecimals": params.token0_decimals,
+            "destDecimals": params.token1_decimals
+        });
+
+        maybe_paraswap_api_error(
+            params
+                .client_aggregator
+                .post(url)
+                .json(&body)
+                .send()
+                .await?
+                .json()
+                .await?,
+        )
+    }
+
     pub async fn simulate_swap_paraswap(
         params: ParaswapParams,
-    ) -> Result<(BigInt, String, Address), String> {
-        let mut res_amount = BigInt::from(0);
-        let mut res_data = String::from("None");
-        let mut res_to = Address::zero();
-
-        let url = format!("https://apiv5.paraswap.io/prices?srcToken=0x{:x}&srcDecimals={}&destToken=0x{:x}&destDecimals={}&amount={}&side={}&network={}&maxImpact=10",
-            params.token_in, params.token0_decimals, params.token_out, params.token1_decimals, params.amount_in, params.side, params.chain_id);
-
-        let res = params
-            .client_aggregator
-            .get(url)
-            .send()
-            .await
-            .map_err(|err| err.to_string())?;
-
-        let body = res.text().await.map_err(|err| err.to_string())?;
-        let json_value = serde_json::from_str::<Value>(&body).map_err(|err| err.to_string())?;
-
-        match json_value.get("priceRoute") {
-            Some(json) => {
-                let (amount_in, amount_out, mode) = if params.side == "SELL".to_string() {
-                    (params.amount_in.clone(), BigInt::from(1), "destAmount")
-                } else {
-                    let agg_amount = json
-                        .get("srcAmount")
-                        .ok_or("Failed to get destination srcAmount")?
-                        .to_string()
-                        .trim_matches('"')
-                        .to_string();
-                    (
-                        BigInt::from_str(&agg_amount).unwrap() * BigInt::from(2),
-                        params.amount_in.clone(),
-                        "srcAmount",
-                    )
-                };
-
-                let dest_amount = json.get(mode).ok_or("Failed to get destination amount")?;
-
-                res_amount =
-                    BigInt::from_str(&format!("{}", dest_amount.to_string().trim_matches('"')))
-                        .map_err(|err| err.to_string())?;
-
-                let url = format!(
-                    "https://apiv5.paraswap.io/transactions/{}?gasPrice=50000000000&ignoreChecks=true&ignoreGasEstimate=true&onlyParams=false", params.chain_id
-                );
-
-                let body_0 = serde_json::json!({
-                    "srcToken": format!("0x{:x}", params.token_in),
-                    "destToken": format!("0x{:x}", params.token_out),
-                    "srcAmount": format!("{}", amount_in),
-                    "destAmount": format!("{}", amount_out),
-                    "priceRoute": json,
-                    "userAddress": format!("0x{:x}", params.wallet_address),
-                    "txOrigin": format!("0x{:x}", params.receiver_address),
-                    //"receiver": format!("0x{:x}", *MY_SC),
-                    "partner": "paraswap.io",
-                    "srcDecimals": params.token0_decimals,
-                    "destDecimals": params.token1_decimals
-                });
-
-                let res = params
-                    .client_aggregator
-                    .post(url)
-                    .json(&body_0)
-                    .send()
+    ) -> Result<(PriceRoute, TransactionResponse), ParaswapError> {
+        let price_route = paraswap_price_route(&params).await?;
+        let transaction = paraswap_transaction(&params, &price_route).await?;
+        Ok((price_route, transaction))
+    }
+
+    /// Paraswap aggregator on one of the `EVM_NETWORKS`
+    pub struct ParaswapRouter {
+        pub network: &'static EvmNetwork,
+    }
+
+    impl ParaswapRouter {
+        async fn paraswap_params(&self, params: &SwapParams) -> Result<ParaswapParams, String> {
+            let provider = evm_context(self.network).await?.provider.clone();
+
+            let token_in = Address::from_str(&params.token_in)
+                .map_err(|e| format!("Invalid token_in address: {}", e))?;
+            let token_out = Address::from_str(&params.token_out)
+                .map_err(|e| format!("Invalid token_out address: {}", e))?;
+            let token0_decimals =
+                get_evm_token_decimals(&ERC20::new(token_in, provider.clone())).await;
+            let token1_decimals =
+                get_evm_token_decimals(&ERC20::new(token_out, provider.clone())).await;
+            let gas_price = match env::var("PARASWAP_GAS_PRICE")
+                .ok()
+                .and_then(|gas_price| gas_price.parse::<u128>().ok())
+            {
+                Some(gas_price) => Some(gas_price),
+                None => provider
+                    .get_gas_price()
+                    .await
+                    .ok()
+                    .map(|gas_price| gas_price.as_u128()),
+            };
+
+            Ok(ParaswapParams {
+                side: match params.side {
+                    SwapSide::ExactIn => "SELL".to_string(),
+                    SwapSide::ExactOut => "BUY".to_string(),
+                },
+                chain_id: self.network.paraswap_network,
+                amount_in: params.amount.clone(),
+                token_in,
+                token_out,
+                token0_decimals: token0_decimals as u32,
+                token1_decimals: token1_decimals as u32,
+                wallet_address: Address::from_str(&params.sender)
+                    .map_err(|e| format!("Invalid wallet address: {}", e))?,
+                receiver_address: Address::from_str(&params.receiver)
+                    .map_err(|e| format!("Invalid receiver address: {}", e))?,
+                slippage_bps: params.slippage_bps,
+                max_impact: env::var("PARASWAP_MAX_IMPACT")
+                    .ok()
+                    .and_then(|max_impact| max_impact.parse::<u32>().ok())
+                    .unwrap_or(DEFAULT_MAX_IMPACT),
+                gas_price,
+                partner: env::var("PARASWAP_PARTNER")
+                    .unwrap_or_else(|_| DEFAULT_PARTNER.to_string()),
+                client_aggregator: Client::new(),
+            })
+        }
+    }
+
+    impl SwapRouter for ParaswapRouter {
+        fn name(&self) -> &'static str {
+            "paraswap"
+        }
+
+        fn supported_chain(&self) -> &'static str {
+            self.network.name
+        }
+
+        fn quote<'a>(
+            &'a self,
+            params: &'a SwapParams,
+        ) -> BoxFuture<'a, Result<SwapQuote, String>> {
+            Box::pin(async move {
+                let paraswap_params = self.paraswap_params(params).await?;
+                let price_route = paraswap_price_route(&paraswap_params)
                     .await
-                    .map_err(|err| err.to_string())?;
-
-                let body = res.text().await.map_err(|err| err.to_string())?;
-
-                let json_value = serde_json::from_str::<serde_json::Value>(&body)
-                    .map_err(|err| err.to_string())?;
-
-                match json_value.get("to") {
-                    Some(address) => {
-                        res_to =
-                            Address::from_str(address.as_str().ok_or("Failed to get address")?)
-                                .map_err(|err| err.to_string())?;
-                        let data = json_value.get("data").ok_or("Failed to get data")?;
-                        res_data = format!("{}", data.to_string().trim_matches('"'));
-                    }
-                    None => {
-                        println!(
-                            "Failed getting calldata in Paraswap (weird): {:#}",
-                            json_value
-                        );
-                    }
-                }
-            }
-            None => {
-                println!(
-                    "Failed getting price in Paraswap (maybe token doesn't exist): {:#}",
-                    body
-                );
-            }
+                    .map_err(|e| e.to_string())?;
+
+                Ok(SwapQuote {
+                    router: self.name(),
+                    amount_in: price_route.src_amount,
+                    amount_out: price_route.dest_amount,
+                    gas_cost_usd: price_route.gas_cost_usd,
+                })
+            })
         }
 
-        Ok((res_amount, res_data, res_to))
+        fn build_swap<'a>(
+            &'a self,
+            params: &'a SwapParams,
+        ) -> BoxFuture<'a, Result<SwapTransaction, String>> {
+            Box::pin(async move {
+                let paraswap_params = self.paraswap_params(params).await?;
+                let (price_route, transaction) = simulate_swap_paraswap(paraswap_params)
+                    .await
+                    .map_err(|e| e.to_string())?;
+
+                Ok(SwapTransaction::Evm {
+                    to: transaction.to,
+                    data: transaction.data,
+                    value: U256::from(transaction.value),
+                    allowance_target: price_route.token_transfer_proxy,
+                    amount_in: price_route.src_amount,
+                })
+            })
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn slippage_bounds_the_quoted_amounts() {
+            let quoted = BigInt::from(1_000_000);
+
+            assert_eq!(min_dest_amount(&quoted, 100), BigInt::from(990_000));
+            assert_eq!(max_src_amount(&quoted, 100), BigInt::from(1_010_000));
+            assert_eq!(min_dest_amount(&quoted, 20_000), BigInt::from(0));
+        }
     }
 }
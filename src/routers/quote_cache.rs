@@ -0,0 +1,238 @@
+use crate::chains::is_evm_chain;
+use crate::routers::swap_router::SwapQuote;
+use num_bigint::BigInt;
+use num_traits::Zero;
+use std::collections::HashMap;
+use std::env;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+const DEFAULT_QUOTE_CACHE_TTL: u64 = 10; // in seconds
+const DEFAULT_QUOTE_CACHE_PRECISION: usize = 4; // significant digits of amount_in, buckets ~0.1% wide
+
+// <chain, token_in, token_out, (amount_in digits, amount_in leading digits)>
+type QuoteKey = (String, String, String, (usize, String));
+
+#[derive(Debug, Clone)]
+struct CachedQuote {
+    quote: SwapQuote,
+    quoted_at: Instant,
+}
+
+/// Recent router quotes, reused for amounts that share the same leading digits
+/// so bursts of similar intents do not hit the aggregators' rate limits.
+#[derive(Debug)]
+pub struct QuoteCache {
+    ttl: Duration,
+    precision: usize,
+    entries: RwLock<HashMap<QuoteKey, CachedQuote>>,
+}
+
+impl QuoteCache {
+    pub fn new(ttl: Duration, precision: usize) -> Self {
+        QuoteCache {
+            ttl,
+            precision: precision.max(1),
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let ttl = env::var("QUOTE_CACHE_TTL")
+            .ok()
+            .and_then(|ttl| ttl.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_QUOTE_CACHE_TTL);
+        let precision = env::var("QUOTE_CACHE_PRECISION")
+            .ok()
+            .and_then(|precision| precision.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_QUOTE_CACHE_PRECISION);
+
+        QuoteCache::new(Duration::from_secs(ttl), precision)
+    }
+
+    fn key(&self, chain: &str, token_in: &str, token_out: &str, amount_in: &BigInt) -> QuoteKey {
+        let digits = amount_in.to_string();
+        let leading = digits[..digits.len().min(self.precision)].to_string();
+        // EVM addresses may come in any checksum casing, Solana mints are
+        // case-sensitive base58
+        let token = |token: &str| {
+            if is_evm_chain(chain) {
+                token.to_lowercase()
+            } else {
+                token.to_string()
+            }
+        };
+
+        (
+            chain.to_string(),
+            token(token_in),
+            token(token_out),
+            (digits.len(), leading),
+        )
+    }
+
+    /// Cached quote for `amount_in`, scaled from the amount that was quoted.
+    /// Price impact makes a bigger amount_in buy less per unit, so the scaled
+    /// amount_out never goes above the amount actually quoted.
+    pub async fn get(
+        &self,
+        chain: &str,
+        token_in: &str,
+        token_out: &str,
+        amount_in: &BigInt,
+    ) -> Option<SwapQuote> {
+        if self.ttl.is_zero() {
+            return None;
+        }
+
+        let key = self.key(chain, token_in, token_out, amount_in);
+        let entries = self.entries.read().await;
+        let cached = entries.get(&key)?;
+        if cached.quoted_at.elapsed() > self.ttl || cached.quote.amount_in.is_zero() {
+            return None;
+        }
+
+        let scaled = &cached.quote.amount_out * amount_in / &cached.quote.amount_in;
+        Some(SwapQuote {
+            amount_in: amount_in.clone(),
+            amount_out: scaled.min(cached.quote.amount_out.clone()),
+            ..cached.quote.clone()
+        })
+    }
+
+    pub async fn insert(
+        &self,
+        chain: &str,
+        token_in: &str,
+        token_out: &str,
+        quote: &SwapQuote,
+    ) {
+        if self.ttl.is_zero() {
+            return;
+        }
+
+        let key = self.key(chain, token_in, token_out, &quote.amount_in);
+        let mut entries = self.entries.write().await;
+        entries.retain(|_, cached| cached.quoted_at.elapsed() <= self.ttl);
+        entries.insert(
+            key,
+            CachedQuote {
+                quote: quote.clone(),
+                quoted_at: Instant::now(),
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USDT: &str = "0xdAC17F958D2ee523a2206206994597C13D831ec7";
+    const WETH: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+
+    fn quote(amount_in: u64, amount_out: u64) -> SwapQuote {
+        SwapQuote {
+            router: "test",
+            amount_in: BigInt::from(amount_in),
+            amount_out: BigInt::from(amount_out),
+            gas_cost_usd: 0.5,
+        }
+    }
+
+    #[test]
+    fn amounts_share_a_bucket_by_digit_count_and_leading_digits() {
+        let cache = QuoteCache::new(Duration::from_secs(10), 2);
+        let key = |amount: u64| cache.key("ethereum", USDT, WETH, &BigInt::from(amount));
+
+        assert_eq!(key(1_200_000), key(1_299_999));
+        assert_ne!(key(1_200_000), key(1_300_000));
+        assert_ne!(key(1_200_000), key(12_000_000));
+    }
+
+    #[test]
+    fn evm_tokens_ignore_checksum_casing() {
+        let cache = QuoteCache::new(Duration::from_secs(10), 2);
+        let amount = BigInt::from(1_000);
+
+        assert_eq!(
+            cache.key("ethereum", USDT, WETH, &amount),
+            cache.key(
+                "ethereum",
+                &USDT.to_lowercase(),
+                &WETH.to_uppercase(),
+                &amount
+            )
+        );
+    }
+
+    #[test]
+    fn solana_mints_stay_case_sensitive() {
+        let cache = QuoteCache::new(Duration::from_secs(10), 2);
+        let amount = BigInt::from(1_000);
+        let mint = "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB";
+
+        assert_ne!(
+            cache.key("solana", mint, mint, &amount),
+            cache.key("solana", &mint.to_lowercase(), mint, &amount)
+        );
+    }
+
+    #[test]
+    fn default_buckets_are_a_tenth_of_a_percent_wide() {
+        let cache = QuoteCache::new(Duration::from_secs(10), DEFAULT_QUOTE_CACHE_PRECISION);
+        let key = |amount: u64| cache.key("ethereum", USDT, WETH, &BigInt::from(amount));
+
+        assert_eq!(key(1_234_000), key(1_234_999));
+        assert_ne!(key(1_234_000), key(1_235_000));
+    }
+
+    #[tokio::test]
+    async fn cached_quote_is_scaled_down_to_a_smaller_amount() {
+        let cache = QuoteCache::new(Duration::from_secs(10), 2);
+        cache
+            .insert("ethereum", USDT, WETH, &quote(1_250_000, 625_000))
+            .await;
+
+        let cached = cache
+            .get("ethereum", USDT, WETH, &BigInt::from(1_200_000))
+            .await
+            .unwrap();
+        assert_eq!(cached.amount_in, BigInt::from(1_200_000));
+        assert_eq!(cached.amount_out, BigInt::from(600_000));
+        assert_eq!(cached.gas_cost_usd, 0.5);
+
+        assert!(cache
+            .get("ethereum", USDT, WETH, &BigInt::from(1_300_000))
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn cached_quote_is_not_scaled_above_the_quoted_amount() {
+        let cache = QuoteCache::new(Duration::from_secs(10), 2);
+        cache
+            .insert("ethereum", USDT, WETH, &quote(1_200_000, 600_000))
+            .await;
+
+        let cached = cache
+            .get("ethereum", USDT, WETH, &BigInt::from(1_250_000))
+            .await
+            .unwrap();
+        assert_eq!(cached.amount_in, BigInt::from(1_250_000));
+        assert_eq!(cached.amount_out, BigInt::from(600_000));
+    }
+
+    #[tokio::test]
+    async fn zero_ttl_disables_the_cache() {
+        let cache = QuoteCache::new(Duration::ZERO, 2);
+        cache
+            .insert("ethereum", USDT, WETH, &quote(1_200_000, 600_000))
+            .await;
+
+        assert!(cache
+            .get("ethereum", USDT, WETH, &BigInt::from(1_200_000))
+            .await
+            .is_none());
+    }
+}
@@ -0,0 +1,223 @@
+use crate::chains::ethereum::ethereum_chain::EVM_NETWORKS;
+use crate::routers::jupiter::JupiterRouter;
+use crate::routers::paraswap::paraswap_router::ParaswapRouter;
+use crate::routers::uniswap_v3::uniswap_v3_router::UniswapV3Router;
+use crate::routers::zeroex::zeroex_router::ZeroExRouter;
+use ethers::types::{Address, U256};
+use futures::future::{join_all, BoxFuture};
+use lazy_static::lazy_static;
+use num_bigint::BigInt;
+use num_traits::{ToPrimitive, Zero};
+use solana_sdk::transaction::VersionedTransaction;
+use std::collections::HashMap;
+use std::env;
+use std::time::Duration;
+
+const DEFAULT_ROUTER_QUOTE_TIMEOUT: u64 = 3_000; // in milliseconds
+
+lazy_static! {
+    // <chain, routers>, in order of registration
+    pub static ref SWAP_ROUTERS: HashMap<&'static str, Vec<Box<dyn SwapRouter>>> = {
+        let mut routers: Vec<Box<dyn SwapRouter>> = EVM_NETWORKS
+            .iter()
+            .map(|network| Box::new(ParaswapRouter { network }) as Box<dyn SwapRouter>)
+            .collect();
+        routers.push(Box::new(ZeroExRouter));
+        routers.push(Box::new(UniswapV3Router));
+        routers.push(Box::new(JupiterRouter));
+        register_routers(routers)
+    };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapSide {
+    ExactIn,
+    ExactOut,
+}
+
+#[derive(Debug, Clone)]
+pub struct SwapParams {
+    pub token_in: String,
+    pub token_out: String,
+    pub amount: BigInt, // amount_in on ExactIn, amount_out on ExactOut
+    pub side: SwapSide,
+    pub sender: String, // wallet paying token_in
+    pub receiver: String, // wallet receiving token_out
+    pub slippage_bps: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct SwapQuote {
+    pub router: &'static str,
+    pub amount_in: BigInt,
+    pub amount_out: BigInt,
+    pub gas_cost_usd: f64,
+}
+
+impl SwapQuote {
+    /// amount_out minus the swap gas cost, priced through the bridge token
+    /// (6 decimals) which is token_in when `bridge_token_in` and token_out otherwise.
+    pub fn net_amount_out(&self, bridge_token_in: bool) -> BigInt {
+        let gas_cost = BigInt::from((self.gas_cost_usd * 1e6).round().to_u128().unwrap_or_default());
+        let gas_cost = if !bridge_token_in {
+            gas_cost
+        } else if self.amount_in.is_zero() {
+            return BigInt::zero();
+        } else {
+            gas_cost * &self.amount_out / &self.amount_in
+        };
+
+        &self.amount_out - gas_cost
+    }
+}
+
+/// Unsigned transaction executing a swap, submitted by the chain module.
+#[derive(Debug, Clone)]
+pub enum SwapTransaction {
+    Evm {
+        to: Address,
+        data: String,
+        value: U256,
+        allowance_target: Address, // spender token_in must be approved to
+        amount_in: BigInt, // expected token_in spent
+    },
+    Solana(VersionedTransaction),
+}
+
+pub trait SwapRouter: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn supported_chain(&self) -> &'static str;
+
+    fn quote<'a>(&'a self, params: &'a SwapParams) -> BoxFuture<'a, Result<SwapQuote, String>>;
+
+    fn build_swap<'a>(
+        &'a self,
+        params: &'a SwapParams,
+    ) -> BoxFuture<'a, Result<SwapTransaction, String>>;
+}
+
+pub fn register_routers(
+    routers: Vec<Box<dyn SwapRouter>>,
+) -> HashMap<&'static str, Vec<Box<dyn SwapRouter>>> {
+    let mut registry: HashMap<&'static str, Vec<Box<dyn SwapRouter>>> = HashMap::new();
+    for router in routers {
+        registry
+            .entry(router.supported_chain())
+            .or_default()
+            .push(router);
+    }
+    registry
+}
+
+pub fn routers_for(chain: &str) -> &'static [Box<dyn SwapRouter>] {
+    SWAP_ROUTERS
+        .get(chain)
+        .map(|routers| routers.as_slice())
+        .unwrap_or_default()
+}
+
+pub fn router_for(chain: &str) -> Result<&'static dyn SwapRouter, String> {
+    routers_for(chain)
+        .first()
+        .map(|router| router.as_ref())
+        .ok_or_else(|| format!("No swap router registered for {chain}"))
+}
+
+pub fn select_router(chain: &str, name: Option<&str>) -> Result<&'static dyn SwapRouter, String> {
+    match name {
+        Some(name) => routers_for(chain)
+            .iter()
+            .find(|router| router.name() == name)
+            .map(|router| router.as_ref())
+            .ok_or_else(|| format!("Swap router {name} not registered for {chain}")),
+        None => router_for(chain),
+    }
+}
+
+/// Quotes every router registered for `chain` in parallel and keeps the one
+/// with the best output net of gas. Routers slower than `ROUTER_QUOTE_TIMEOUT`
+/// are skipped.
+pub async fn best_quote(
+    chain: &str,
+    params: &SwapParams,
+    bridge_token_in: bool,
+) -> Result<SwapQuote, String> {
+    let timeout = Duration::from_millis(
+        env::var("ROUTER_QUOTE_TIMEOUT")
+            .ok()
+            .and_then(|timeout| timeout.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_ROUTER_QUOTE_TIMEOUT),
+    );
+
+    let routers = routers_for(chain);
+    if routers.is_empty() {
+        return Err(format!("No swap router registered for {chain}"));
+    }
+
+    let results = join_all(routers.iter().map(|router| async move {
+        match tokio::time::timeout(timeout, router.quote(params)).await {
+            Ok(result) => result.map_err(|e| format!("{}: {}", router.name(), e)),
+            Err(_) => Err(format!("{}: timed out", router.name())),
+        }
+    }))
+    .await;
+
+    let mut best: Option<SwapQuote> = None;
+    let mut errors = Vec::new();
+    for result in results {
+        match result {
+            Ok(quote) => {
+                let better = match &best {
+                    Some(best) => {
+                        quote.net_amount_out(bridge_token_in) > best.net_amount_out(bridge_token_in)
+                    }
+                    None => true,
+                };
+                if better {
+                    best = Some(quote);
+                }
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+
+    best.ok_or_else(|| errors.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote(amount_in: u64, amount_out: u64, gas_cost_usd: f64) -> SwapQuote {
+        SwapQuote {
+            router: "test",
+            amount_in: BigInt::from(amount_in),
+            amount_out: BigInt::from(amount_out),
+            gas_cost_usd,
+        }
+    }
+
+    #[test]
+    fn net_amount_out_subtracts_gas_in_bridge_token_out() {
+        // 1.5 USDT of gas off 1000 USDT out
+        let quote = quote(500_000_000_000_000_000, 1_000_000_000, 1.5);
+        assert_eq!(quote.net_amount_out(false), BigInt::from(998_500_000u64));
+    }
+
+    #[test]
+    fn net_amount_out_converts_gas_at_the_quote_price_for_bridge_token_in() {
+        // 1000 USDT in for 0.5 WETH out, 2 USDT of gas is 0.001 WETH
+        let quote = quote(1_000_000_000, 500_000_000_000_000_000, 2.0);
+        assert_eq!(
+            quote.net_amount_out(true),
+            BigInt::from(499_000_000_000_000_000u64)
+        );
+    }
+
+    #[test]
+    fn net_amount_out_is_zero_without_amount_in() {
+        let quote = quote(0, 1_000, 1.0);
+        assert_eq!(quote.net_amount_out(true), BigInt::zero());
+    }
+}
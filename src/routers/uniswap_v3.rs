@@ -0,0 +1,338 @@
+pub mod uniswap_v3_router {
+    use crate::chains::ethereum::ethereum_chain::{evm_context, evm_network};
+    use crate::chains::get_token_info;
+    use crate::routers::swap_router::{
+        SwapParams, SwapQuote, SwapRouter, SwapSide, SwapTransaction,
+    };
+    use ethers::prelude::{abigen, Address, Bytes, Http, Middleware, Provider, U256};
+    use futures::future::{join_all, BoxFuture};
+    use num_bigint::BigInt;
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    abigen!(
+        QuoterV2,
+        r#"[
+            function quoteExactInput(bytes path, uint256 amountIn) external returns (uint256 amountOut, uint160[] sqrtPriceX96AfterList, uint32[] initializedTicksCrossedList, uint256 gasEstimate)
+            function quoteExactOutput(bytes path, uint256 amountOut) external returns (uint256 amountIn, uint160[] sqrtPriceX96AfterList, uint32[] initializedTicksCrossedList, uint256 gasEstimate)
+        ]"#
+    );
+
+    abigen!(
+        SwapRouter02,
+        r#"[
+            struct ExactInputParams { bytes path; address recipient; uint256 amountIn; uint256 amountOutMinimum; }
+            struct ExactOutputParams { bytes path; address recipient; uint256 amountOut; uint256 amountInMaximum; }
+            function exactInput(ExactInputParams params) external payable returns (uint256 amountOut)
+            function exactOutput(ExactOutputParams params) external payable returns (uint256 amountIn)
+        ]"#
+    );
+
+    pub const QUOTER_V2: &str = "0x61fFE014bA17989E743c5F6cB21bF9697530B21e";
+    pub const SWAP_ROUTER_02: &str = "0x68b3465833fb72A70ecDF485E0e4C7bD8665Fc45";
+    pub const WETH: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+
+    // in hundredths of a bip
+    const FEE_TIERS: [u32; 4] = [100, 500, 3_000, 10_000];
+    const WETH_USDT_FEE: u32 = 500;
+
+    /// Pool path in swap order, `fees[i]` is the pool between `tokens[i]` and `tokens[i + 1]`.
+    #[derive(Debug, Clone)]
+    struct Route {
+        tokens: Vec<Address>,
+        fees: Vec<u32>,
+    }
+
+    impl Route {
+        /// Packed `token | fee (uint24) | token ...` path. QuoterV2 and
+        /// SwapRouter02 expect it reversed (token_out first) on exact output.
+        fn path(&self, side: SwapSide) -> Bytes {
+            let (tokens, fees): (Vec<Address>, Vec<u32>) = match side {
+                SwapSide::ExactIn => (self.tokens.clone(), self.fees.clone()),
+                SwapSide::ExactOut => (
+                    self.tokens.iter().rev().cloned().collect(),
+                    self.fees.iter().rev().cloned().collect(),
+                ),
+            };
+
+            let mut path = Vec::with_capacity(tokens.len() * 23);
+            for (i, token) in tokens.iter().enumerate() {
+                path.extend_from_slice(token.as_bytes());
+                if let Some(fee) = fees.get(i) {
+                    path.extend_from_slice(&fee.to_be_bytes()[1..]);
+                }
+            }
+            Bytes::from(path)
+        }
+    }
+
+    /// Direct pools on every fee tier, plus two hops through WETH.
+    fn candidate_routes(token_in: Address, token_out: Address, weth: Address) -> Vec<Route> {
+        let mut routes: Vec<Route> = FEE_TIERS
+            .iter()
+            .map(|fee| Route {
+                tokens: vec![token_in, token_out],
+                fees: vec![*fee],
+            })
+            .collect();
+
+        if token_in != weth && token_out != weth {
+            for fee_in in &FEE_TIERS[1..] {
+                for fee_out in &FEE_TIERS[1..] {
+                    routes.push(Route {
+                        tokens: vec![token_in, weth, token_out],
+                        fees: vec![*fee_in, *fee_out],
+                    });
+                }
+            }
+        }
+
+        routes
+    }
+
+    fn to_u256(amount: &BigInt) -> Result<U256, String> {
+        U256::from_dec_str(&amount.to_string()).map_err(|e| format!("Invalid amount: {}", e))
+    }
+
+    fn to_bigint(amount: U256) -> BigInt {
+        BigInt::from_str(&amount.to_string()).unwrap_or_default()
+    }
+
+    /// Uniswap V3 on Ethereum mainnet, quoted on-chain through QuoterV2 so it
+    /// only needs `ETHEREUM_RPC`.
+    pub struct UniswapV3Router;
+
+    impl UniswapV3Router {
+        async fn provider(&self) -> Result<Arc<Provider<Http>>, String> {
+            let network = evm_network("ethereum")
+                .ok_or_else(|| "ethereum is not an EVM network".to_string())?;
+            Ok(evm_context(network).await?.provider.clone())
+        }
+
+        /// Returns (amount_out on ExactIn or amount_in on ExactOut, gas estimate).
+        async fn quote_route(
+            &self,
+            quoter: &QuoterV2<Provider<Http>>,
+            route: &Route,
+            side: SwapSide,
+            amount: U256,
+        ) -> Result<(U256, U256), String> {
+            let path = route.path(side);
+            let result = match side {
+                SwapSide::ExactIn => quoter.quote_exact_input(path, amount).call().await,
+                SwapSide::ExactOut => quoter.quote_exact_output(path, amount).call().await,
+            };
+            result
+                .map(|(amount, _, _, gas_estimate)| (amount, gas_estimate))
+                .map_err(|e| e.to_string())
+        }
+
+        /// Quotes every candidate route and keeps the one giving the most
+        /// token_out (ExactIn) or asking the least token_in (ExactOut).
+        async fn best_route(
+            &self,
+            provider: Arc<Provider<Http>>,
+            params: &SwapParams,
+        ) -> Result<(Route, U256, U256), String> {
+            let quoter = QuoterV2::new(
+                Address::from_str(QUOTER_V2).map_err(|e| e.to_string())?,
+                provider,
+            );
+            let token_in = Address::from_str(&params.token_in)
+                .map_err(|e| format!("Invalid token_in address: {}", e))?;
+            let token_out = Address::from_str(&params.token_out)
+                .map_err(|e| format!("Invalid token_out address: {}", e))?;
+            let weth = Address::from_str(WETH).map_err(|e| e.to_string())?;
+            let amount = to_u256(&params.amount)?;
+
+            let routes = candidate_routes(token_in, token_out, weth);
+            let results = join_all(
+                routes
+                    .iter()
+                    .map(|route| self.quote_route(&quoter, route, params.side, amount)),
+            )
+            .await;
+
+            routes
+                .into_iter()
+                .zip(results)
+                .filter_map(|(route, result)| result.ok().map(|(amount, gas)| (route, amount, gas)))
+                .filter(|(_, amount, _)| !amount.is_zero())
+                .reduce(|best, candidate| {
+                    let better = match params.side {
+                        SwapSide::ExactIn => candidate.1 > best.1,
+                        SwapSide::ExactOut => candidate.1 < best.1,
+                    };
+                    if better {
+                        candidate
+                    } else {
+                        best
+                    }
+                })
+                .ok_or_else(|| "Uniswap V3 has no pool for this pair".to_string())
+        }
+
+        /// ETH price in USD, quoted on the WETH/USDT pool.
+        async fn eth_usd_price(&self, provider: Arc<Provider<Http>>) -> Result<f64, String> {
+            let (usdt, _) = get_token_info("USDT", "ethereum")
+                .ok_or_else(|| "Failed to get token info".to_string())?;
+            let quoter = QuoterV2::new(
+                Address::from_str(QUOTER_V2).map_err(|e| e.to_string())?,
+                provider,
+            );
+            let route = Route {
+                tokens: vec![
+                    Address::from_str(WETH).map_err(|e| e.to_string())?,
+                    Address::from_str(usdt).map_err(|e| e.to_string())?,
+                ],
+                fees: vec![WETH_USDT_FEE],
+            };
+            let (usdt_out, _) = self
+                .quote_route(
+                    &quoter,
+                    &route,
+                    SwapSide::ExactIn,
+                    U256::exp10(18),
+                )
+                .await?;
+            Ok(usdt_out.as_u128() as f64 / 1e6)
+        }
+    }
+
+    impl SwapRouter for UniswapV3Router {
+        fn name(&self) -> &'static str {
+            "uniswap_v3"
+        }
+
+        fn supported_chain(&self) -> &'static str {
+            "ethereum"
+        }
+
+        fn quote<'a>(
+            &'a self,
+            params: &'a SwapParams,
+        ) -> BoxFuture<'a, Result<SwapQuote, String>> {
+            Box::pin(async move {
+                let provider = self.provider().await?;
+                let (_route, quoted, gas_estimate) =
+                    self.best_route(provider.clone(), params).await?;
+
+                // gasEstimate only covers the pool swaps, not the transaction overhead
+                let gas_price = provider.get_gas_price().await.map_err(|e| e.to_string())?;
+                let eth_price = self.eth_usd_price(provider).await?;
+                let gas_cost_usd =
+                    gas_estimate.as_u128() as f64 * gas_price.as_u128() as f64 * eth_price / 1e18;
+
+                let (amount_in, amount_out) = match params.side {
+                    SwapSide::ExactIn => (params.amount.clone(), to_bigint(quoted)),
+                    SwapSide::ExactOut => (to_bigint(quoted), params.amount.clone()),
+                };
+                Ok(SwapQuote {
+                    router: self.name(),
+                    amount_in,
+                    amount_out,
+                    gas_cost_usd,
+                })
+            })
+        }
+
+        fn build_swap<'a>(
+            &'a self,
+            params: &'a SwapParams,
+        ) -> BoxFuture<'a, Result<SwapTransaction, String>> {
+            Box::pin(async move {
+                let provider = self.provider().await?;
+                let (route, quoted, _) = self.best_route(provider.clone(), params).await?;
+
+                let swap_router_02 = Address::from_str(SWAP_ROUTER_02).map_err(|e| e.to_string())?;
+                let recipient = Address::from_str(&params.receiver)
+                    .map_err(|e| format!("Invalid receiver address: {}", e))?;
+                let amount = to_u256(&params.amount)?;
+                let slippage_bps = U256::from(params.slippage_bps);
+                let router = SwapRouter02::new(swap_router_02, provider);
+
+                let calldata = match params.side {
+                    SwapSide::ExactIn => router
+                        .exact_input(ExactInputParams {
+                            path: route.path(params.side),
+                            recipient,
+                            amount_in: amount,
+                            amount_out_minimum: quoted * (U256::from(10_000) - slippage_bps)
+                                / U256::from(10_000),
+                        })
+                        .calldata(),
+                    SwapSide::ExactOut => router
+                        .exact_output(ExactOutputParams {
+                            path: route.path(params.side),
+                            recipient,
+                            amount_out: amount,
+                            amount_in_maximum: quoted * (U256::from(10_000) + slippage_bps)
+                                / U256::from(10_000),
+                        })
+                        .calldata(),
+                }
+                .ok_or_else(|| "Failed to encode SwapRouter02 calldata".to_string())?;
+
+                Ok(SwapTransaction::Evm {
+                    to: swap_router_02,
+                    data: format!("0x{}", hex::encode(calldata)),
+                    value: U256::zero(),
+                    allowance_target: swap_router_02,
+                    amount_in: match params.side {
+                        SwapSide::ExactIn => params.amount.clone(),
+                        SwapSide::ExactOut => to_bigint(quoted),
+                    },
+                })
+            })
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn token(byte: u8) -> Address {
+            Address::repeat_byte(byte)
+        }
+
+        #[test]
+        fn path_packs_tokens_and_fees_in_swap_order() {
+            let route = Route {
+                tokens: vec![token(0xaa), token(0xbb)],
+                fees: vec![3_000],
+            };
+            let path = route.path(SwapSide::ExactIn);
+
+            assert_eq!(path.len(), 20 + 3 + 20);
+            assert_eq!(&path[..20], token(0xaa).as_bytes());
+            assert_eq!(&path[20..23], &[0x00, 0x0b, 0xb8]);
+            assert_eq!(&path[23..], token(0xbb).as_bytes());
+        }
+
+        #[test]
+        fn path_is_reversed_for_exact_output() {
+            let route = Route {
+                tokens: vec![token(0xaa), token(0xee), token(0xbb)],
+                fees: vec![500, 10_000],
+            };
+            let path = route.path(SwapSide::ExactOut);
+
+            assert_eq!(path.len(), 3 * 20 + 2 * 3);
+            assert_eq!(&path[..20], token(0xbb).as_bytes());
+            assert_eq!(&path[20..23], &[0x00, 0x27, 0x10]);
+            assert_eq!(&path[23..43], token(0xee).as_bytes());
+            assert_eq!(&path[43..46], &[0x00, 0x01, 0xf4]);
+            assert_eq!(&path[46..], token(0xaa).as_bytes());
+        }
+
+        #[test]
+        fn candidate_routes_skip_weth_hops_for_weth_pairs() {
+            let weth = token(0xee);
+            assert_eq!(
+                candidate_routes(token(0xaa), token(0xbb), weth).len(),
+                4 + 3 * 3
+            );
+            assert_eq!(candidate_routes(weth, token(0xbb), weth).len(), 4);
+        }
+    }
+}
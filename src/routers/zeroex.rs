@@ -0,0 +1,184 @@
+pub mod zeroex_router {
+    use crate::chains::get_token_info;
+    use crate::routers::jupiter::field_as_string;
+    use crate::routers::swap_router::{
+        SwapParams, SwapQuote, SwapRouter, SwapSide, SwapTransaction,
+    };
+    use ethers::prelude::{Address, U256};
+    use futures::future::BoxFuture;
+    use num_bigint::BigInt;
+    use reqwest::Client;
+    use serde::de::DeserializeOwned;
+    use serde::Deserialize;
+    use std::env;
+
+    // Reference: https://0x.org/docs/0x-swap-api/api-references/get-swap-v1-quote
+    fn zeroex_api_url() -> String {
+        env::var("ZEROX_API_URL").unwrap_or_else(|_| "https://api.0x.org".to_string())
+    }
+
+    #[derive(thiserror::Error, Debug)]
+    pub enum ZeroExError {
+        #[error("reqwest: {0}")]
+        Reqwest(#[from] reqwest::Error),
+
+        #[error("0x API: {0}")]
+        ZeroExApi(String),
+
+        #[error("serde_json: {0}")]
+        SerdeJson(#[from] serde_json::Error),
+    }
+
+    #[derive(Clone, Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ZeroExSource {
+        pub name: String,
+        #[serde(with = "field_as_string")]
+        pub proportion: f64,
+    }
+
+    /// Response of `/swap/v1/price`, an indicative quote without calldata.
+    #[derive(Clone, Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ZeroExPrice {
+        #[serde(with = "field_as_string")]
+        pub sell_amount: BigInt,
+        #[serde(with = "field_as_string")]
+        pub buy_amount: BigInt,
+        #[serde(with = "field_as_string")]
+        pub estimated_gas: u64,
+        #[serde(with = "field_as_string")]
+        pub gas_price: u128,
+        // token units (decimals applied) worth 1 ETH
+        #[serde(with = "field_as_string")]
+        pub sell_token_to_eth_rate: f64,
+        #[serde(with = "field_as_string")]
+        pub buy_token_to_eth_rate: f64,
+        pub allowance_target: Address,
+        pub sources: Vec<ZeroExSource>,
+    }
+
+    /// Response of `/swap/v1/quote`, a firm quote with the calldata to submit.
+    #[derive(Clone, Debug, Deserialize)]
+    pub struct ZeroExQuote {
+        #[serde(flatten)]
+        pub price: ZeroExPrice,
+        pub to: Address,
+        pub data: String,
+        #[serde(with = "field_as_string")]
+        pub value: u128,
+    }
+
+    fn maybe_zeroex_api_error<T>(value: serde_json::Value) -> Result<T, ZeroExError>
+    where
+        T: DeserializeOwned,
+    {
+        #[derive(Deserialize)]
+        struct ErrorResponse {
+            reason: String,
+        }
+        if let Ok(ErrorResponse { reason }) = serde_json::from_value::<ErrorResponse>(value.clone())
+        {
+            Err(ZeroExError::ZeroExApi(reason))
+        } else {
+            serde_json::from_value(value).map_err(|err| err.into())
+        }
+    }
+
+    /// 0x Swap API on Ethereum mainnet
+    pub struct ZeroExRouter;
+
+    impl ZeroExRouter {
+        async fn request<T: DeserializeOwned>(
+            &self,
+            endpoint: &str,
+            params: &SwapParams,
+        ) -> Result<T, ZeroExError> {
+            let amount_param = match params.side {
+                SwapSide::ExactIn => "sellAmount",
+                SwapSide::ExactOut => "buyAmount",
+            };
+            let url = format!(
+                "{base_url}/swap/v1/{endpoint}?sellToken={}&buyToken={}&{amount_param}={}&takerAddress={}&slippagePercentage={}&skipValidation=true",
+                params.token_in,
+                params.token_out,
+                params.amount,
+                params.sender,
+                params.slippage_bps as f64 / 10_000f64,
+                base_url = zeroex_api_url(),
+            );
+
+            let mut request = Client::new().get(url);
+            if let Ok(api_key) = env::var("ZEROX_API_KEY") {
+                request = request.header("0x-api-key", api_key);
+            }
+
+            maybe_zeroex_api_error(request.send().await?.json().await?)
+        }
+    }
+
+    /// Gas cost of the swap in USD, priced through whichever side is the
+    /// bridge token (USDT). 0x doesn't price gas in USD itself.
+    fn gas_cost_usd(price: &ZeroExPrice, params: &SwapParams) -> f64 {
+        let gas_cost_eth = price.estimated_gas as f64 * price.gas_price as f64 / 1e18;
+        let usd_per_eth = match get_token_info("USDT", "ethereum") {
+            Some((usdt, _)) if usdt.eq_ignore_ascii_case(&params.token_out) => {
+                price.buy_token_to_eth_rate
+            }
+            Some((usdt, _)) if usdt.eq_ignore_ascii_case(&params.token_in) => {
+                price.sell_token_to_eth_rate
+            }
+            _ => 0f64,
+        };
+        gas_cost_eth * usd_per_eth
+    }
+
+    impl SwapRouter for ZeroExRouter {
+        fn name(&self) -> &'static str {
+            "0x"
+        }
+
+        fn supported_chain(&self) -> &'static str {
+            "ethereum"
+        }
+
+        fn quote<'a>(
+            &'a self,
+            params: &'a SwapParams,
+        ) -> BoxFuture<'a, Result<SwapQuote, String>> {
+            Box::pin(async move {
+                let price: ZeroExPrice = self
+                    .request("price", params)
+                    .await
+                    .map_err(|e| e.to_string())?;
+
+                Ok(SwapQuote {
+                    router: self.name(),
+                    amount_in: price.sell_amount.clone(),
+                    amount_out: price.buy_amount.clone(),
+                    gas_cost_usd: gas_cost_usd(&price, params),
+                })
+            })
+        }
+
+        fn build_swap<'a>(
+            &'a self,
+            params: &'a SwapParams,
+        ) -> BoxFuture<'a, Result<SwapTransaction, String>> {
+            Box::pin(async move {
+                let quote: ZeroExQuote = self
+                    .request("quote", params)
+                    .await
+                    .map_err(|e| e.to_string())?;
+
+                Ok(SwapTransaction::Evm {
+                    to: quote.to,
+                    data: quote.data,
+                    value: U256::from(quote.value),
+                    allowance_target: quote.price.allowance_target,
+                    amount_in: quote.price.sell_amount,
+                })
+            })
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use crate::chains::PostIntentInfo;
+use crate::routers::QuoteResult;
+use crate::strategies::{bid_with_margin, comission_from_env, BidDecision, BidStrategy, Inventory};
+use num_bigint::BigInt;
+
+/// Keeps `comission / 100_000` of the src chain swap output on every intent.
+#[derive(Debug, Clone)]
+pub struct FixedCommission {
+    pub comission: u32,
+}
+
+impl FixedCommission {
+    pub fn from_env() -> Self {
+        FixedCommission {
+            comission: comission_from_env(),
+        }
+    }
+}
+
+impl BidStrategy for FixedCommission {
+    fn name(&self) -> &'static str {
+        "fixed_commission"
+    }
+
+    fn bid(
+        &self,
+        intent: &PostIntentInfo,
+        quote: &QuoteResult,
+        _inventory: &Inventory,
+    ) -> BidDecision {
+        let margin = &quote.src_amount_out * BigInt::from(self.comission) / BigInt::from(100_000);
+        bid_with_margin(intent, quote, &margin)
+    }
+}
@@ -0,0 +1,155 @@
+use crate::chains::PostIntentInfo;
+use crate::routers::QuoteResult;
+use crate::strategies::{bid_with_margin, comission_from_env, BidDecision, BidStrategy, Inventory};
+use num_bigint::BigInt;
+use num_traits::{ToPrimitive, Zero};
+use std::env;
+
+/// Charges more when the destination chain is short on bridge token and less
+/// when it holds more than its even share, so inventory drifts back to balance.
+#[derive(Debug, Clone)]
+pub struct InventorySkew {
+    pub comission: u32,
+    pub skew: u32, // extra comission when dst chain inventory is empty
+}
+
+impl InventorySkew {
+    pub fn from_env() -> Self {
+        let comission = comission_from_env();
+        let skew = env::var("INVENTORY_SKEW")
+            .ok()
+            .and_then(|skew| skew.parse::<u32>().ok())
+            .unwrap_or(comission);
+
+        InventorySkew { comission, skew }
+    }
+}
+
+impl BidStrategy for InventorySkew {
+    fn name(&self) -> &'static str {
+        "inventory_skew"
+    }
+
+    fn bid(
+        &self,
+        intent: &PostIntentInfo,
+        quote: &QuoteResult,
+        inventory: &Inventory,
+    ) -> BidDecision {
+        // the even share below is 1 / number of chains
+        if inventory.balances.is_empty() {
+            return BidDecision::Decline("inventory is unknown on every chain".to_string());
+        }
+        let dst_balance = match inventory.balance(&intent.dst_chain) {
+            Some(balance) => balance,
+            None => {
+                return BidDecision::Decline(format!(
+                    "inventory on {} is unknown",
+                    intent.dst_chain
+                ))
+            }
+        };
+        if *dst_balance < quote.bridge_amount {
+            return BidDecision::Decline(format!(
+                "not enough inventory on {}: {dst_balance}",
+                intent.dst_chain
+            ));
+        }
+
+        let total = inventory.total();
+        let target = 1.0 / inventory.balances.len() as f64;
+        let share = if total.is_zero() {
+            target
+        } else {
+            dst_balance.to_f64().unwrap_or_default() / total.to_f64().unwrap_or(1.0)
+        };
+
+        // 1.0 when dst chain is empty, 0.0 at its even share, negative above it
+        let deviation = (target - share) / target;
+        let comission = (self.comission as f64 + self.skew as f64 * deviation).max(0.0);
+
+        let margin = BigInt::from(
+            (quote.src_amount_out.to_f64().unwrap_or_default() * comission / 100_000.0)
+                .floor()
+                .to_u128()
+                .unwrap_or_default(),
+        );
+        bid_with_margin(intent, quote, &margin)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategies::tests::{intent, quote};
+    use num_bigint::BigInt;
+    use std::collections::HashMap;
+
+    const SKEW: InventorySkew = InventorySkew {
+        comission: 100, // 0.1%
+        skew: 100,
+    };
+
+    fn inventory(balances: &[(&str, u64)]) -> Inventory {
+        Inventory {
+            balances: balances
+                .iter()
+                .map(|(chain, balance)| (chain.to_string(), BigInt::from(*balance)))
+                .collect::<HashMap<_, _>>(),
+        }
+    }
+
+    fn skew_bid(strategy: &InventorySkew, inventory: &Inventory) -> BidDecision {
+        strategy.bid(&intent("ethereum", "0"), &quote(1_000_000), inventory)
+    }
+
+    fn bid(commission: u64) -> BidDecision {
+        BidDecision::Bid {
+            amount_out: BigInt::from(1_000_000 - commission),
+            commission: BigInt::from(commission),
+        }
+    }
+
+    #[test]
+    fn charges_the_base_comission_at_an_even_share() {
+        let even = inventory(&[("ethereum", 2_000_000), ("arbitrum", 2_000_000)]);
+        assert_eq!(skew_bid(&SKEW, &even), bid(1_000));
+    }
+
+    #[test]
+    fn charges_more_below_the_even_share() {
+        // a quarter held against a half target, deviation 0.5
+        let short = inventory(&[("ethereum", 1_000_000), ("arbitrum", 3_000_000)]);
+        assert_eq!(skew_bid(&SKEW, &short), bid(1_500));
+    }
+
+    #[test]
+    fn charges_less_above_the_even_share() {
+        // three quarters held against a half target, deviation -0.5
+        let long = inventory(&[("ethereum", 3_000_000), ("arbitrum", 1_000_000)]);
+        assert_eq!(skew_bid(&SKEW, &long), bid(500));
+    }
+
+    #[test]
+    fn clamps_the_comission_at_zero() {
+        let strategy = InventorySkew {
+            comission: 100,
+            skew: 200,
+        };
+        let all = inventory(&[("ethereum", 4_000_000), ("arbitrum", 0)]);
+        assert_eq!(skew_bid(&strategy, &all), bid(0));
+    }
+
+    #[test]
+    fn declines_without_enough_or_any_inventory() {
+        let short = inventory(&[("ethereum", 999_999), ("arbitrum", 4_000_000)]);
+        let unknown_dst = inventory(&[("arbitrum", 4_000_000)]);
+
+        for inventory in [short, unknown_dst, Inventory::default()] {
+            assert!(matches!(
+                skew_bid(&SKEW, &inventory),
+                BidDecision::Decline(_)
+            ));
+        }
+    }
+}
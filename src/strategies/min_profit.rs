@@ -0,0 +1,40 @@
+use crate::chains::PostIntentInfo;
+use crate::routers::QuoteResult;
+use crate::strategies::{bid_with_margin, BidDecision, BidStrategy, Inventory};
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+use std::env;
+
+/// Keeps the same absolute amount of bridge token on every intent, whatever its size.
+#[derive(Debug, Clone)]
+pub struct MinProfit {
+    pub min_profit: BigInt, // in bridge token, 6 decimals
+}
+
+impl MinProfit {
+    pub fn from_env() -> Self {
+        let min_profit = env::var("MIN_PROFIT")
+            .expect("MIN_PROFIT must be set")
+            .parse::<f64>()
+            .unwrap();
+
+        MinProfit {
+            min_profit: BigInt::from((min_profit * 1e6).round().to_u128().unwrap()),
+        }
+    }
+}
+
+impl BidStrategy for MinProfit {
+    fn name(&self) -> &'static str {
+        "min_profit"
+    }
+
+    fn bid(
+        &self,
+        intent: &PostIntentInfo,
+        quote: &QuoteResult,
+        _inventory: &Inventory,
+    ) -> BidDecision {
+        bid_with_margin(intent, quote, &self.min_profit)
+    }
+}
@@ -0,0 +1,296 @@
+pub mod fixed_commission;
+pub mod inventory_skew;
+pub mod min_profit;
+
+use crate::chains::ethereum::ethereum_chain::{ethereum_token_balance, EVM_NETWORKS};
+use crate::chains::get_token_info;
+use crate::chains::solana::solana_chain::solana_token_balance;
+use crate::chains::OperationOutput;
+use crate::chains::PostIntentInfo;
+use crate::chains::SOLVER_ADDRESSES;
+use crate::routers::QuoteResult;
+use fixed_commission::FixedCommission;
+use inventory_skew::InventorySkew;
+use lazy_static::lazy_static;
+use min_profit::MinProfit;
+use num_bigint::BigInt;
+use num_traits::Zero;
+use std::collections::HashMap;
+use std::env;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+const DEFAULT_INVENTORY_CACHE_TTL: u64 = 30; // in seconds
+
+lazy_static! {
+    pub static ref BID_STRATEGY: Box<dyn BidStrategy> = strategy_from_env();
+    // <bridge token, (fetched at, inventory)>
+    static ref INVENTORY_CACHE: RwLock<HashMap<String, (Instant, Inventory)>> =
+        RwLock::new(HashMap::new());
+    static ref INVENTORY_CACHE_TTL: Duration = Duration::from_secs(
+        env::var("INVENTORY_CACHE_TTL")
+            .ok()
+            .and_then(|ttl| ttl.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_INVENTORY_CACHE_TTL)
+    );
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BidDecision {
+    Bid {
+        amount_out: BigInt, // token_out offered to the user
+        commission: BigInt, // bridge token kept by the solver
+    },
+    Decline(String),
+}
+
+// Bridge token held by the solver on each chain
+#[derive(Debug, Clone, Default)]
+pub struct Inventory {
+    pub balances: HashMap<String, BigInt>,
+}
+
+impl Inventory {
+    /// Balances of `bridge_token` on every chain, reused for
+    /// `INVENTORY_CACHE_TTL` seconds so quoting doesn't hit every RPC per intent.
+    pub async fn fetch(bridge_token: &str) -> Inventory {
+        if let Some((fetched_at, inventory)) = INVENTORY_CACHE.read().await.get(bridge_token) {
+            if fetched_at.elapsed() < *INVENTORY_CACHE_TTL {
+                return inventory.clone();
+            }
+        }
+
+        let inventory = Inventory::fetch_balances(bridge_token).await;
+        INVENTORY_CACHE
+            .write()
+            .await
+            .insert(bridge_token.to_string(), (Instant::now(), inventory.clone()));
+        inventory
+    }
+
+    /// Drops the cached balances, e.g. after a fill moved them.
+    pub async fn invalidate() {
+        INVENTORY_CACHE.write().await.clear();
+    }
+
+    async fn fetch_balances(bridge_token: &str) -> Inventory {
+        let mut balances = HashMap::new();
+
+        for network in EVM_NETWORKS.iter().filter(|network| network.rpc_url().is_ok()) {
+            if let Some((token, _)) = get_token_info(bridge_token, network.name) {
+                match ethereum_token_balance(network, token, SOLVER_ADDRESSES[0]).await {
+                    Ok(balance) => {
+                        balances.insert(
+                            network.name.to_string(),
+                            BigInt::from_str(&balance.to_string()).unwrap(),
+                        );
+                    }
+                    Err(e) => eprintln!(
+                        "Error fetching {bridge_token} inventory on {}: {e}",
+                        network.name
+                    ),
+                }
+            }
+        }
+
+        if let Some((token, _)) = get_token_info(bridge_token, "solana") {
+            match solana_token_balance(token, SOLVER_ADDRESSES[1]).await {
+                Ok(balance) => {
+                    balances.insert("solana".to_string(), BigInt::from(balance));
+                }
+                Err(e) => eprintln!("Error fetching {bridge_token} inventory on solana: {e}"),
+            }
+        }
+
+        Inventory { balances }
+    }
+
+    pub fn balance(&self, chain: &str) -> Option<&BigInt> {
+        self.balances.get(chain)
+    }
+
+    pub fn total(&self) -> BigInt {
+        self.balances.values().sum()
+    }
+}
+
+pub trait BidStrategy: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn bid(
+        &self,
+        intent: &PostIntentInfo,
+        quote: &QuoteResult,
+        inventory: &Inventory,
+    ) -> BidDecision;
+}
+
+pub fn strategy_from_env() -> Box<dyn BidStrategy> {
+    let strategy = env::var("BID_STRATEGY").unwrap_or_else(|_| "fixed_commission".to_string());
+
+    match strategy.as_str() {
+        "fixed_commission" => Box::new(FixedCommission::from_env()),
+        "min_profit" => Box::new(MinProfit::from_env()),
+        "inventory_skew" => Box::new(InventorySkew::from_env()),
+        _ => panic!("BID_STRATEGY {strategy} not supported"),
+    }
+}
+
+// COMISSION is expressed in 1/100_000 of the bridge amount, "1" -> 0.001%
+pub fn comission_from_env() -> u32 {
+    env::var("COMISSION")
+        .expect("COMISSION must be set")
+        .parse::<u32>()
+        .unwrap()
+}
+
+pub fn amount_out_min(intent: &PostIntentInfo) -> BigInt {
+    match &intent.outputs {
+        OperationOutput::SwapTransfer(output) => {
+            BigInt::from_str(&output.amount_out).unwrap_or_default()
+        }
+        _ => BigInt::zero(),
+    }
+}
+
+// Offers the quoted token_out scaled down by the share of bridge token the
+// solver keeps, and declines when it no longer covers the user's minimum.
+pub fn bid_with_margin(
+    intent: &PostIntentInfo,
+    quote: &QuoteResult,
+    margin: &BigInt,
+) -> BidDecision {
+    if quote.dst_amount_out.is_zero() || quote.bridge_amount.is_zero() {
+        return BidDecision::Decline("swap size does not cover FLAT_FEES".to_string());
+    }
+    if *margin >= quote.bridge_amount {
+        return BidDecision::Decline("swap size does not cover FLAT_FEES + margin".to_string());
+    }
+
+    let final_amount =
+        &quote.dst_amount_out * (&quote.bridge_amount - margin) / &quote.bridge_amount;
+    let amount_out_min = amount_out_min(intent);
+    if final_amount <= amount_out_min {
+        return BidDecision::Decline(format!(
+            "user wants {amount_out_min} token_out, you can provide {final_amount} token_out"
+        ));
+    }
+
+    BidDecision::Bid {
+        amount_out: final_amount,
+        commission: margin.clone(),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::chains::{OperationInput, SwapTransferInput, SwapTransferOutput};
+    use crate::routers::FeeInfo;
+    use std::time::SystemTime;
+
+    // bridge token and token_out at par, no fees
+    pub(crate) fn quote(bridge_amount: u64) -> QuoteResult {
+        QuoteResult {
+            src_amount_out: BigInt::from(bridge_amount),
+            fees: FeeInfo {
+                store_intent: 0.0,
+                send_funds_to_user: 0.0,
+                swap_and_send_funds_to_user: 0.0,
+                on_receive_transfer: 0.0,
+                relayer_fee: 0.0,
+                token_account_rent: 0.0,
+            },
+            new_token_accounts: 0,
+            flat_fee: BigInt::zero(),
+            bridge_amount: BigInt::from(bridge_amount),
+            commission: BigInt::zero(),
+            dst_amount_out: BigInt::from(bridge_amount),
+            dst_bridge_token: String::new(),
+            routes: Vec::new(),
+            src_router: None,
+            dst_router: None,
+            quoted_at: SystemTime::now(),
+        }
+    }
+
+    pub(crate) fn intent(dst_chain: &str, amount_out: &str) -> PostIntentInfo {
+        PostIntentInfo {
+            function_name: "transfer".to_string(),
+            src_chain: "arbitrum".to_string(),
+            dst_chain: dst_chain.to_string(),
+            inputs: OperationInput::SwapTransfer(SwapTransferInput {
+                token_in: String::new(),
+                amount_in: "1000000".to_string(),
+                src_chain_user: String::new(),
+                timeout: String::new(),
+            }),
+            outputs: OperationOutput::SwapTransfer(SwapTransferOutput {
+                token_out: String::new(),
+                amount_out: amount_out.to_string(),
+                dst_chain_user: String::new(),
+            }),
+        }
+    }
+
+    #[test]
+    fn bid_keeps_the_margin_share_of_token_out() {
+        assert_eq!(
+            bid_with_margin(
+                &intent("ethereum", "998999"),
+                &quote(1_000_000),
+                &BigInt::from(1_000)
+            ),
+            BidDecision::Bid {
+                amount_out: BigInt::from(999_000),
+                commission: BigInt::from(1_000),
+            }
+        );
+    }
+
+    #[test]
+    fn bid_declines_a_margin_eating_the_bridge_amount() {
+        for margin in [1_000_000, 1_000_001] {
+            assert!(matches!(
+                bid_with_margin(
+                    &intent("ethereum", "0"),
+                    &quote(1_000_000),
+                    &BigInt::from(margin)
+                ),
+                BidDecision::Decline(_)
+            ));
+        }
+    }
+
+    #[test]
+    fn bid_declines_when_it_does_not_beat_the_user_minimum() {
+        for amount_out_min in ["999000", "999001"] {
+            assert!(matches!(
+                bid_with_margin(
+                    &intent("ethereum", amount_out_min),
+                    &quote(1_000_000),
+                    &BigInt::from(1_000)
+                ),
+                BidDecision::Decline(_)
+            ));
+        }
+    }
+
+    #[test]
+    fn strategy_is_selected_by_bid_strategy() {
+        // the only test touching these variables, so setting them doesn't race
+        env::set_var("COMISSION", "100");
+        env::set_var("MIN_PROFIT", "1.5");
+        env::remove_var("BID_STRATEGY");
+        assert_eq!(strategy_from_env().name(), "fixed_commission");
+
+        for name in ["fixed_commission", "min_profit", "inventory_skew"] {
+            env::set_var("BID_STRATEGY", name);
+            assert_eq!(strategy_from_env().name(), name);
+        }
+
+        env::set_var("BID_STRATEGY", "unknown");
+        assert!(std::panic::catch_unwind(strategy_from_env).is_err());
+    }
+}